@@ -1,22 +1,53 @@
 use std::borrow::Borrow;
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
 use std::mem;
 
 use fnv::FnvHasher;
 
+/// The error returned by `Map::try_reserve`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested bucket count does not fit in memory.
+    CapacityOverflow,
+    /// The allocator could not provide the buckets.
+    AllocError,
+}
+
+/// The hasher a `Map` uses unless given another.
+pub type DefaultHashBuilder = BuildHasherDefault<FnvHasher>;
+
 #[derive(Clone)]
-pub(crate) struct Map<K, V> {
+pub struct Map<K, V, S = DefaultHashBuilder> {
     buckets: Vec<Vec<(K, V)>>,
     items: usize,
-    SIZE: Option<usize>,
+    size: Option<usize>,
+    hash_builder: S,
 }
 
 impl<K, V> Map<K, V> {
     pub fn new(bucket_size: Option<usize>) -> Self {
+        Map::with_hasher(bucket_size, DefaultHashBuilder::default())
+    }
+}
+
+impl<K, V, S> Map<K, V, S> {
+    /// Create an empty map that hashes keys with `hash_builder`.
+    pub fn with_hasher(bucket_size: Option<usize>, hash_builder: S) -> Self {
         Map {
             buckets: Vec::new(),
             items: 0,
-            SIZE: bucket_size,
+            size: bucket_size,
+            hash_builder,
+        }
+    }
+
+    // walks every bucket to check `items` is the real count, this costs
+    // nothing in release builds
+    fn check_items(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let actual = self.buckets.iter().map(Vec::len).sum::<usize>();
+            assert_eq!(actual, self.items, "Map::items out of sync with the buckets");
         }
     }
 }
@@ -25,47 +56,180 @@ impl<K, V> Map<K, V>
 where
     K: Hash + Eq,
 {
+    /// Create a map that holds `capacity` distinct keys without resizing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity == 0 {
+            return Map::new(None);
+        }
+        // smallest power of two bucket count with capacity under 3/4 load
+        let buckets = capacity
+            .checked_mul(4)
+            .map(|n| n.div_ceil(3).next_power_of_two())
+            .expect("capacity overflow");
+        let mut map = Map::new(Some(buckets));
+        map.resize();
+        map
+    }
+}
+
+impl<K, V, S> Map<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn hash<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.hash_builder.hash_one(key)
+    }
+
     fn bucket<Q>(&self, key: &Q) -> usize
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let mut hasher = FnvHasher::default();
-        key.hash(&mut hasher);
-        let h_key = hasher.finish();
-        let bucket_idx = (h_key % self.buckets.len() as u64) as usize;
-        println!(
-            "bucket: {} % {} = {}",
-            h_key,
-            self.buckets.len(),
-            bucket_idx
-        );
-        bucket_idx
+        self.bucket_of(self.hash(key))
+    }
+
+    fn bucket_of(&self, h_key: u64) -> usize {
+        (h_key % self.buckets.len() as u64) as usize
+    }
+
+    /// The hash the map uses for `key`, useful for routing keys across
+    /// several maps with the same hasher.
+    pub fn hash_of<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.hash(key)
+    }
+
+    /// The bucket `key` falls in right now, this changes whenever the map
+    /// resizes.
+    ///
+    /// Panics if no buckets are allocated yet.
+    pub fn bucket_for<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        assert!(!self.buckets.is_empty(), "map has no buckets");
+        self.bucket(key)
+    }
+
+    // true if one more item would push the load over 3/4
+    fn needs_resize(&self) -> bool {
+        self.buckets.is_empty() || (self.items + 1) * 4 > self.buckets.len() * 3
     }
 
     fn resize(&mut self) {
+        // bucket counts are always a power of two whatever the initial size
         let target_size = match self.buckets.len() {
             0 => {
-                if let Some(size) = self.SIZE {
-                    size
-                } else {
-                    // TODO a sensible default??
-                    1
-                }
+                // TODO a sensible default??
+                self.size.unwrap_or(1).next_power_of_two()
             }
             // bucket size doubles
-            n => 2 * n,
+            n => (2 * n).next_power_of_two(),
         };
         let mut new_buckets = Vec::with_capacity(target_size);
         new_buckets.extend((0..target_size).map(|_| Vec::new()));
+        self.rehash_into(new_buckets);
+        self.check_items();
+    }
 
+    fn rehash_into(&mut self, mut new_buckets: Vec<Vec<(K, V)>>) {
         for (k, v) in self.buckets.iter_mut().flat_map(|bucket| bucket.drain(..)) {
-            let mut hasher = FnvHasher::default();
-            k.hash(&mut hasher);
-            let bucket_idx = (hasher.finish() % new_buckets.len() as u64) as usize;
+            let bucket_idx = (self.hash_builder.hash_one(&k) % new_buckets.len() as u64) as usize;
             new_buckets[bucket_idx].push((k, v));
         }
-        mem::replace(&mut self.buckets, new_buckets);
+        self.buckets = new_buckets;
+    }
+
+    /// Swap in a new hasher, e.g. a reseeded one after a run of collisions,
+    /// and move every entry to its bucket under the new hash. The bucket
+    /// count and items stay the same.
+    pub fn rehash_with(&mut self, hash_builder: S) {
+        self.hash_builder = hash_builder;
+        let mut new_buckets = Vec::with_capacity(self.buckets.len());
+        new_buckets.extend((0..self.buckets.len()).map(|_| Vec::new()));
+        self.rehash_into(new_buckets);
+        self.check_items();
+    }
+
+    /// Grow the buckets so `additional` more items fit under the load
+    /// factor, returning an error instead of aborting if that is impossible.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        // resize happens once items go over 3/4 of the buckets
+        let target_size = self
+            .items
+            .checked_add(additional)
+            .and_then(|n| n.checked_mul(4))
+            .and_then(|n| n.div_ceil(3).checked_next_power_of_two())
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if target_size <= self.buckets.len() {
+            return Ok(());
+        }
+        target_size
+            .checked_mul(mem::size_of::<Vec<(K, V)>>())
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        let mut new_buckets = Vec::new();
+        new_buckets
+            .try_reserve_exact(target_size)
+            .map_err(|_| TryReserveError::AllocError)?;
+        new_buckets.extend((0..target_size).map(|_| Vec::new()));
+        self.rehash_into(new_buckets);
+        self.check_items();
+        Ok(())
+    }
+
+    /// Grow the buckets so `additional` more items fit under the load factor.
+    ///
+    /// Panics if the bucket count overflows or allocation fails.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(err) = self.try_reserve(additional) {
+            panic!("Map::reserve failed: {:?}", err);
+        }
+    }
+
+    /// Shrink to the fewest buckets that fit the items under the load factor.
+    ///
+    /// The largest bucket allocations are kept and refilled rather than
+    /// starting from new empty buckets.
+    pub fn shrink_to_fit(&mut self) {
+        let target_size = (self.items * 4).div_ceil(3).next_power_of_two();
+        if target_size >= self.buckets.len() {
+            return;
+        }
+
+        let mut buckets = mem::take(&mut self.buckets);
+        buckets.sort_unstable_by_key(|bucket| std::cmp::Reverse(bucket.capacity()));
+
+        // the biggest dropped bucket is scratch space for every entry
+        let mut scratch = Vec::new();
+        for mut bucket in buckets.drain(target_size..) {
+            if scratch.capacity() == 0 {
+                mem::swap(&mut scratch, &mut bucket);
+            } else {
+                scratch.append(&mut bucket);
+            }
+        }
+        for bucket in buckets.iter_mut() {
+            scratch.append(bucket);
+        }
+        buckets.shrink_to_fit();
+
+        self.buckets = buckets;
+        for (k, v) in scratch.drain(..) {
+            let bucket_idx = self.bucket(&k);
+            self.buckets[bucket_idx].push((k, v));
+        }
+        self.check_items();
     }
 
     /// Number of items in the hashmap.
@@ -78,6 +242,20 @@ where
         self.items == 0
     }
 
+    /// Number of buckets currently allocated.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Items per bucket, 0 when no buckets are allocated.
+    pub fn load_factor(&self) -> f64 {
+        if self.buckets.is_empty() {
+            0.0
+        } else {
+            self.items as f64 / self.buckets.len() as f64
+        }
+    }
+
     /// Returns true if map contains given key.
     pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
@@ -89,56 +267,171 @@ where
 
     /// Insert key value pair into hashmap.
     pub fn insert(&mut self, key: K, val: V) -> Option<V> {
-        //                           total > 3 * 
-        if self.buckets.is_empty() || self.items > 3 * self.buckets.len() / 4 {
+        if self.needs_resize() {
             self.resize();
         }
 
         let bucket_idx = self.bucket(&key);
         let bucket = &mut self.buckets[bucket_idx];
 
-        self.items += 1;
         for (ekey, eval) in bucket.iter_mut() {
             if ekey == &key {
                 return Some(mem::replace(eval, val));
             }
         }
         bucket.push((key, val));
+        self.items += 1;
+        self.check_items();
         None
     }
 
-    /// Insert key value pair into hashmap.
-    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
-        let bucket_idx = self.bucket(&key);
-        // let bucket = &mut self.buckets[bucket_idx];
+    /// Insert key value pair only if the key is missing, otherwise both are
+    /// handed back untouched.
+    pub fn insert_if_absent(&mut self, key: K, val: V) -> Result<(), (K, V)> {
+        let hash = self.hash(&key);
+        if !self.buckets.is_empty() {
+            let bucket_idx = self.bucket_of(hash);
+            if self.buckets[bucket_idx].iter().any(|(k, _)| k == &key) {
+                return Err((key, val));
+            }
+        }
+        self.insert_hashed(hash, key, val);
+        Ok(())
+    }
 
-        if let Some(entry) = self.buckets[bucket_idx]
-            .iter_mut()
-            .find(|(k, _)| k == &key)
+    /// Get the entry for key, the key is hashed once and the hash is
+    /// kept in the entry for any insert that follows.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash(&key);
+
+        if !self.buckets.is_empty() {
+            let bucket_idx = self.bucket_of(hash);
+            if let Some(item_idx) = self.buckets[bucket_idx]
+                .iter()
+                .position(|(k, _)| k == &key)
             {
-                return Entry::Occupied( OccEntry {
-                    entry: unsafe { &mut *(entry as *mut _) }
-                } );
-            };
+                return Entry::Occupied(OccEntry { map: self, hash, bucket_idx, item_idx });
+            }
+        }
+
+        Entry::Vacant(VacEntry { map: self, hash, key })
+    }
 
-        Entry::Vacant( VacEntry { key, bucket: &mut self.buckets[bucket_idx], } )
+    /// Resolve the entry for each key in turn and hand it to `f`.
+    ///
+    /// Room for the iterator's lower size hint is reserved once up front so
+    /// a counting loop like `map.entries(words, |e| *e.or_insert(0) += 1)`
+    /// does not grow the buckets part way through. Duplicate keys mean the
+    /// reservation can overshoot, `shrink_to_fit` gives it back.
+    pub fn entries<I, F>(&mut self, keys: I, mut f: F)
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(Entry<'_, K, V, S>),
+    {
+        let keys = keys.into_iter();
+        self.reserve(keys.size_hint().0);
+        for key in keys {
+            f(self.entry(key));
+        }
+    }
+
+    /// Get the entry for a borrowed key, an owned `K` is only made from
+    /// `key` if the entry is vacant and gets inserted into.
+    pub fn entry_ref<'a, 'q, Q>(&'a mut self, key: &'q Q) -> EntryRef<'a, 'q, K, Q, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash(key);
+
+        if !self.buckets.is_empty() {
+            let bucket_idx = self.bucket_of(hash);
+            if let Some(item_idx) = self.buckets[bucket_idx]
+                .iter()
+                .position(|(k, _)| k.borrow() == key)
+            {
+                return EntryRef::Occupied(OccEntry { map: self, hash, bucket_idx, item_idx });
+            }
+        }
+
+        EntryRef::Vacant(VacEntryRef { map: self, hash, key })
+    }
+
+    fn insert_hashed(&mut self, hash: u64, key: K, val: V) -> &mut V {
+        if self.needs_resize() {
+            self.resize();
+        }
+
+        let bucket_idx = self.bucket_of(hash);
+        self.items += 1;
+        self.buckets[bucket_idx].push((key, val));
+        self.check_items();
+        &mut self.buckets[bucket_idx].last_mut().unwrap().1
     }
 
     /// Iterator over keys and values.
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
         Iter {
+            buckets: &self.buckets,
             bucket_idx: 0,
             item_idx: 0,
-            map: self,
         }
     }
 
+    /// Iterator over keys.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// Iterator over values.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Sum of all values.
+    pub fn sum_values(&self) -> V
+    where
+        V: std::iter::Sum + Copy,
+    {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.iter().map(|(_, v)| *v))
+            .sum()
+    }
+
+    /// Iterator over keys and mutable values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            buckets: self.buckets.iter_mut(),
+            bucket: [].iter_mut(),
+        }
+    }
+
+    /// Iterator over the entries whose value passes `pred`.
+    pub fn filter_values<'a, F>(&'a self, mut pred: F) -> impl Iterator<Item = (&'a K, &'a V)> + 'a
+    where
+        F: FnMut(&V) -> bool + 'a,
+    {
+        self.iter().filter(move |(_, v)| pred(v))
+    }
+
+    /// Iterator over the entries whose key passes `pred`.
+    pub fn filter_keys<'a, F>(&'a self, mut pred: F) -> impl Iterator<Item = (&'a K, &'a V)> + 'a
+    where
+        F: FnMut(&K) -> bool + 'a,
+    {
+        self.iter().filter(move |(k, _)| pred(k))
+    }
+
     /// Get value from key.
     pub fn get<Q>(&self, key: &Q) -> Option<&V>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
+        if self.buckets.is_empty() {
+            return None;
+        }
         let bucket_idx = self.bucket(key.borrow());
         self.buckets[bucket_idx]
             .iter()
@@ -152,6 +445,9 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
+        if self.buckets.is_empty() {
+            return None;
+        }
         let bucket_idx = self.bucket(key.borrow());
         self.buckets[bucket_idx]
             .iter_mut()
@@ -159,30 +455,168 @@ where
             .map(|(_, v)| v)
     }
 
+    /// Get the stored key and a mutable value from key.
+    pub fn get_key_value_mut<Q>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let bucket_idx = self.bucket(key);
+        self.buckets[bucket_idx]
+            .iter_mut()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(k, v)| (&*k, v))
+    }
+
+    /// Get value from key, inserting `default()` if the key is missing.
+    ///
+    /// The key is only cloned when it has to be inserted.
+    pub fn get_mut_or<F>(&mut self, key: &K, default: F) -> &mut V
+    where
+        K: Clone,
+        F: FnOnce() -> V,
+    {
+        let hash = self.hash(key);
+        if !self.buckets.is_empty() {
+            let bucket_idx = self.bucket_of(hash);
+            if let Some(idx) = self.buckets[bucket_idx].iter().position(|(k, _)| k == key) {
+                return &mut self.buckets[bucket_idx][idx].1;
+            }
+        }
+
+        self.insert_hashed(hash, key.clone(), default())
+    }
+
     /// Removes key value pair based on key.
     pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let bucket_idx = self.bucket(&key);
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let bucket_idx = self.bucket(key);
         let bucket = &mut self.buckets[bucket_idx];
         let idx = bucket.iter().position(|(k, _)| k.borrow() == key)?;
+        let (_, val) = bucket.swap_remove(idx);
         self.items -= 1;
-        Some(bucket.swap_remove(idx).1)
+        self.check_items();
+        Some(val)
+    }
+
+    /// Moves every entry into a `Vector` sorted by key.
+    #[cfg(feature = "vector")]
+    pub fn into_sorted_vector(mut self) -> vector::Vector<(K, V)>
+    where
+        K: Ord,
+    {
+        let mut entries = vector::Vector::with_capacity(self.items);
+        for (k, v) in self.buckets.iter_mut().flat_map(|bucket| bucket.drain(..)) {
+            entries.push((k, v));
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries
     }
 
+    /// Keeps only the entries for which `f` returns true.
+    ///
+    /// Removed entries are swapped out so the survivors within a bucket can
+    /// change order, use `retain_stable` to keep iteration order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for bucket in self.buckets.iter_mut() {
+            let mut idx = 0;
+            while idx < bucket.len() {
+                let (k, v) = &mut bucket[idx];
+                if f(k, v) {
+                    idx += 1;
+                } else {
+                    bucket.swap_remove(idx);
+                    self.items -= 1;
+                }
+            }
+        }
+        self.check_items();
+    }
+
+    /// Keeps only the entries for which `f` returns true, the survivors keep
+    /// their relative order within each bucket.
+    pub fn retain_stable<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for bucket in self.buckets.iter_mut() {
+            let before = bucket.len();
+            bucket.retain_mut(|(k, v)| f(k, v));
+            self.items -= before - bucket.len();
+        }
+        self.check_items();
+    }
+
+    /// Removes and yields every entry for which `pred` returns true.
+    ///
+    /// Dropping the iterator early still removes the remaining matching
+    /// entries.
+    pub fn drain_filter<F>(&mut self, pred: F) -> DrainFilter<'_, K, V, S, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        DrainFilter {
+            map: self,
+            pred,
+            bucket_idx: 0,
+            item_idx: 0,
+        }
+    }
+
+    /// Removes all entries and drops the buckets, starting over from the
+    /// initial bucket size.
     pub fn clear(&mut self) {
         self.items = 0;
         self.buckets.clear();
         self.resize();
     }
+
+    /// Removes all entries but keeps every bucket and its allocation, so
+    /// refilling to the same size does not allocate.
+    pub fn clear_keeping_capacity(&mut self) {
+        self.items = 0;
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+        self.check_items();
+    }
+}
+
+impl<K1, K2, V, S1, S2> Map<K1, Map<K2, V, S2>, S1>
+where
+    K1: Hash + Eq,
+    K2: Hash + Eq,
+    S1: BuildHasher,
+    S2: BuildHasher,
+{
+    /// Get value from the inner map, `None` if either key is missing.
+    pub fn get_in(&self, k1: &K1, k2: &K2) -> Option<&V> {
+        self.get(k1)?.get(k2)
+    }
+
+    /// Get mutable value from the inner map, `None` if either key is missing.
+    pub fn get_in_mut(&mut self, k1: &K1, k2: &K2) -> Option<&mut V> {
+        self.get_mut(k1)?.get_mut(k2)
+    }
 }
 
-impl<'a, K, Q, V> std::ops::Index<&Q> for Map<K, V> 
+impl<K, Q, V, S> std::ops::Index<&Q> for Map<K, V, S>
 where
     K: Hash + Eq + Borrow<Q>,
     Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
 {
     type Output = V;
     fn index(&self, key: &Q) -> &V {
@@ -190,28 +624,37 @@ where
     }
 }
 
-impl<K, V> std::fmt::Debug for Map<K, V> 
+impl<K, V, S> std::fmt::Debug for Map<K, V, S>
 where
-    K: std::fmt::Display + Eq + Hash,
-    V: std::fmt::Display + Eq + Hash,
+    K: std::fmt::Debug + Eq + Hash,
+    V: std::fmt::Debug,
+    S: BuildHasher,
 {
+    /// `{:#?}` also prints a summary line with the map's load.
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        writeln!(f, "Map {{")?;
-        writeln!(f, "    buckets:")?;
-        let mut count = 0;
-        for b in self.buckets.iter() {
-            for (k, v) in b.iter() {
-                writeln!(f, "       #{} [ ({}, {}) ],", count, k, v)?;
-                count += 1;
-            }
-            count += 1;
+        f.debug_map().entries(self.iter()).finish()?;
+        if f.alternate() {
+            write!(
+                f,
+                "\nitems: {}, bucket_count: {}, load_factor: {:.2}",
+                self.items,
+                self.bucket_count(),
+                self.load_factor()
+            )?;
         }
-        writeln!(f, "items: {}, SIZE: {:?}\n}}", self.items, self.SIZE)
+        Ok(())
+    }
+}
+
+impl<K, V, S: Default> Default for Map<K, V, S> {
+    /// An empty map with no buckets allocated.
+    fn default() -> Self {
+        Map::with_hasher(None, S::default())
     }
 }
 
 pub struct Iter<'a, K, V> {
-    map: &'a Map<K, V>,
+    buckets: &'a [Vec<(K, V)>],
     bucket_idx: usize,
     item_idx: usize,
 }
@@ -220,7 +663,7 @@ impl<'a, K, V> Iterator for Iter<'a, K, V> {
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match self.map.buckets.get(self.bucket_idx) {
+            match self.buckets.get(self.bucket_idx) {
                 Some(bucket) => match bucket.get(self.item_idx) {
                     Some((k, v)) => {
                         self.item_idx += 1;
@@ -239,44 +682,68 @@ impl<'a, K, V> Iterator for Iter<'a, K, V> {
 }
 
 pub struct IterMut<'a, K, V> {
-    map: Option<&'a mut Map<K, V>>,
-    bucket_idx: usize,
-    item_idx: usize,
+    buckets: std::slice::IterMut<'a, Vec<(K, V)>>,
+    bucket: std::slice::IterMut<'a, (K, V)>,
 }
 
-impl<'a, K, V> IterMut<'a, K, V> {
-    fn iter_mut(&'a mut self) -> Option<(&'a K, &'a mut V)> {
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match self.map.take()?.buckets.get_mut(self.bucket_idx) {
-                Some(bucket) => match bucket.get_mut(self.item_idx) {
-                    Some((ref mut k, v)) => {
-                        self.item_idx += 1;
-                        break Some((k, v));
-                    }
-                    None => {
-                        self.bucket_idx += 1;
-                        self.item_idx = 0;
-                        continue;
-                    }
-                },
-                None => break None,
+            match self.bucket.next() {
+                Some((k, v)) => break Some((&*k, v)),
+                None => self.bucket = self.buckets.next()?.iter_mut(),
             }
         }
     }
 }
 
-impl<'a, K, V> IntoIterator for &'a Map<K, V> {
+impl<'a, K, V, S> IntoIterator for &'a Map<K, V, S> {
     type Item = (&'a K, &'a V);
     type IntoIter = Iter<'a, K, V>;
     fn into_iter(self) -> Self::IntoIter {
         Iter {
-            map: self,
+            buckets: &self.buckets,
             bucket_idx: 0,
             item_idx: 0,
         }
     }
 }
 
+/// Owned iterator over the entries.
+///
+/// Entries come out in ascending bucket order and front to back within a
+/// bucket, which is insertion order unless a `remove` or `retain` swapped
+/// entries around. Two maps built by the same sequence of calls always
+/// yield the same sequence.
+pub struct IntoIter<K, V> {
+    buckets: std::vec::IntoIter<Vec<(K, V)>>,
+    bucket: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.bucket.next() {
+                Some(entry) => break Some(entry),
+                None => self.bucket = self.buckets.next()?.into_iter(),
+            }
+        }
+    }
+}
+
+impl<K, V, S> IntoIterator for Map<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            buckets: self.buckets.into_iter(),
+            bucket: Vec::new().into_iter(),
+        }
+    }
+}
+
 pub struct Keys<'a, K, V> {
     inner: Iter<'a, K, V>,
 }
@@ -301,46 +768,209 @@ impl<'a, K, V> Iterator for Values<'a, K, V> {
     }
 }
 
-pub struct OccEntry<'a, K, V> {
-    entry: &'a mut (K, V),
+pub struct DrainFilter<'a, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    map: &'a mut Map<K, V, S>,
+    pred: F,
+    bucket_idx: usize,
+    item_idx: usize,
 }
 
-pub struct VacEntry<'a, K, V> {
+impl<'a, K, V, S, F> Iterator for DrainFilter<'a, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let bucket = self.map.buckets.get_mut(self.bucket_idx)?;
+            match bucket.get_mut(self.item_idx) {
+                Some((k, v)) => {
+                    if (self.pred)(k, v) {
+                        self.map.items -= 1;
+                        // the swapped in item lands on item_idx and is checked next
+                        break Some(bucket.swap_remove(self.item_idx));
+                    }
+                    self.item_idx += 1;
+                }
+                None => {
+                    self.bucket_idx += 1;
+                    self.item_idx = 0;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K, V, S, F> Drop for DrainFilter<'a, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    fn drop(&mut self) {
+        for _ in &mut *self {}
+        self.map.check_items();
+    }
+}
+
+pub struct OccEntry<'a, K, V, S = DefaultHashBuilder> {
+    map: &'a mut Map<K, V, S>,
+    hash: u64,
+    bucket_idx: usize,
+    item_idx: usize,
+}
+
+impl<'a, K, V, S> OccEntry<'a, K, V, S> {
+    pub fn get(&self) -> &V {
+        &self.map.buckets[self.bucket_idx][self.item_idx].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.buckets[self.bucket_idx][self.item_idx].1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.buckets[self.bucket_idx][self.item_idx].1
+    }
+
+    /// Removes the entry from the map returning the value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry from the map returning the stored key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        let pair = self.map.buckets[self.bucket_idx].swap_remove(self.item_idx);
+        self.map.items -= 1;
+        self.map.check_items();
+        pair
+    }
+}
+
+pub struct VacEntry<'a, K, V, S = DefaultHashBuilder> {
+    map: &'a mut Map<K, V, S>,
+    hash: u64,
     key: K,
-    bucket: &'a mut Vec<(K, V)>,
 }
 
-impl<'a, K, V> VacEntry<'a, K, V> {
+impl<'a, K, V, S> VacEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Insert the value, a resize here reuses the hash from `Map::entry`
+    /// to find the new bucket.
+    pub fn insert(self, val: V) -> &'a mut V {
+        self.map.insert_hashed(self.hash, self.key, val)
+    }
+}
+
+pub struct VacEntryRef<'a, 'q, K, Q: ?Sized, V, S = DefaultHashBuilder> {
+    map: &'a mut Map<K, V, S>,
+    hash: u64,
+    key: &'q Q,
+}
+
+impl<'a, 'q, K, Q, V, S> VacEntryRef<'a, 'q, K, Q, V, S>
+where
+    K: Hash + Eq + From<&'q Q>,
+    Q: ?Sized,
+    S: BuildHasher,
+{
+    /// Insert the value, this is the only place the owned key is made.
     pub fn insert(self, val: V) -> &'a mut V {
-        self.bucket.push((self.key, val));
-        &mut self.bucket.last_mut().unwrap().1
+        self.map.insert_hashed(self.hash, K::from(self.key), val)
     }
 }
 
-pub enum Entry<'a, K, V> {
-    Occupied(OccEntry<'a, K, V>),
-    Vacant(VacEntry<'a, K, V>),
+pub enum Entry<'a, K, V, S = DefaultHashBuilder> {
+    Occupied(OccEntry<'a, K, V, S>),
+    Vacant(VacEntry<'a, K, V, S>),
 }
 
-impl<'a, K, V> Entry<'a, K, V> {
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Removes an occupied entry if `f` returns true for its value, turning
+    /// it into a vacant entry for the same key.
+    pub fn and_remove_if<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&V) -> bool,
+    {
+        match self {
+            Entry::Occupied(entry) if f(entry.get()) => {
+                let hash = entry.hash;
+                let map = &mut *entry.map;
+                map.items -= 1;
+                let (key, _) = map.buckets[entry.bucket_idx].swap_remove(entry.item_idx);
+                map.check_items();
+                Entry::Vacant(VacEntry { map, hash, key })
+            }
+            entry => entry,
+        }
+    }
+
     pub fn or_insert(self, val: V) -> &'a mut V {
         match self {
-            Entry::Occupied(entry) => &mut entry.entry.1,
+            Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => entry.insert(val),
         }
     }
 
+    /// Insert `f()` if vacant. The value is computed before the bucket is
+    /// looked up so a resize caused by the insert is always safe, and the
+    /// entry's borrow of the map rules out `f` touching the same map:
+    ///
+    /// ```compile_fail
+    /// let mut map = hash_map::Map::new(None);
+    /// map.entry(1).or_insert_with(|| {
+    ///     map.insert(2, 2);
+    ///     1
+    /// });
+    /// ```
     pub fn or_insert_with<F>(self, f: F) -> &'a mut V
     where
         F: FnOnce() -> V,
     {
         match self {
-            Entry::Occupied(entry) => &mut entry.entry.1,
+            Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => entry.insert(f()),
         }
     }
 }
 
+pub enum EntryRef<'a, 'q, K, Q: ?Sized, V, S = DefaultHashBuilder> {
+    Occupied(OccEntry<'a, K, V, S>),
+    Vacant(VacEntryRef<'a, 'q, K, Q, V, S>),
+}
+
+impl<'a, 'q, K, Q, V, S> EntryRef<'a, 'q, K, Q, V, S>
+where
+    K: Hash + Eq + From<&'q Q>,
+    Q: ?Sized,
+    S: BuildHasher,
+{
+    pub fn or_insert(self, val: V) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(val),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(f()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -385,6 +1015,554 @@ mod tests {
         assert_eq!(map["poneyland"], 33);
     }
 
+    #[test]
+    fn test_map_get_mut_or() {
+        let mut map: Map<String, u32> = Map::new(None);
+        let key = "a".to_string();
+        *map.get_mut_or(&key, || 0) += 1;
+        *map.get_mut_or(&key, || 0) += 1;
+        *map.get_mut_or(&"b".to_string(), || 10) += 1;
+
+        assert_eq!(map["a"], 2);
+        assert_eq!(map["b"], 11);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_map_entry_hashes_once() {
+        use std::cell::Cell;
+
+        struct Counted<'a>(u32, &'a Cell<usize>);
+        impl PartialEq for Counted<'_> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Counted<'_> {}
+        impl Hash for Counted<'_> {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.1.set(self.1.get() + 1);
+                self.0.hash(state);
+            }
+        }
+
+        let hashes = Cell::new(0);
+        let mut map = Map::new(None);
+        *map.entry(Counted(1, &hashes)).or_insert(1) += 10;
+        assert_eq!(hashes.get(), 1);
+
+        let val = map.entry(Counted(1, &hashes)).or_insert(0);
+        *val *= 2;
+        assert_eq!(*val, 22);
+        assert_eq!(hashes.get(), 2);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_map_filter() {
+        let mut map: Map<&str, u64> = Map::new(None);
+        map.insert("cpu", 90);
+        map.insert("mem", 40);
+        map.insert("disk", 75);
+
+        let mut high = map.filter_values(|v| *v > 50).map(|(k, _)| *k).collect::<Vec<_>>();
+        high.sort();
+        assert_eq!(high, vec!["cpu", "disk"]);
+
+        let short = map.filter_keys(|k| k.len() == 3).count();
+        assert_eq!(short, 2);
+        assert_eq!(map.filter_values(|_| false).count(), 0);
+    }
+
+    #[test]
+    fn test_map_debug() {
+        let mut map = Map::new(Some(4));
+        map.insert("a", 1);
+        map.insert("b", 2);
+
+        let normal = format!("{:?}", map);
+        assert!(normal.contains("\"a\": 1"));
+        assert!(!normal.contains("bucket_count"));
+
+        let alt = format!("{:#?}", map);
+        assert!(alt.contains("\"b\": 2"));
+        assert!(alt.ends_with("items: 2, bucket_count: 4, load_factor: 0.50"));
+    }
+
+    #[test]
+    #[cfg(feature = "vector")]
+    fn test_map_into_sorted_vector() {
+        let mut map = Map::new(None);
+        for (k, v) in [(3, 'c'), (1, 'a'), (4, 'd'), (2, 'b')].iter() {
+            map.insert(*k, *v);
+        }
+
+        let entries = map.into_sorted_vector();
+        assert_eq!(&entries[..], &[(1, 'a'), (2, 'b'), (3, 'c'), (4, 'd')][..]);
+        assert_eq!(entries.capacity(), 4);
+    }
+
+    #[test]
+    fn test_map_drain_filter_early_drop() {
+        let mut map = Map::new(None);
+        for i in 0..20 {
+            map.insert(i, i * 10);
+        }
+
+        let mut drain = map.drain_filter(|k, _| k % 2 == 0);
+        let (k, v) = drain.next().unwrap();
+        assert_eq!(k * 10, v);
+        drop(drain);
+
+        assert_eq!(map.len(), 10);
+        assert!((0..20).filter(|k| k % 2 == 0).all(|k| !map.contains_key(&k)));
+        assert!((0..20).filter(|k| k % 2 == 1).all(|k| map[&k] == k * 10));
+    }
+
+    #[test]
+    fn test_map_get_key_value_mut() {
+        let mut map: Map<String, Vec<u32>> = Map::new(None);
+        map.insert("first".to_string(), vec![1]);
+        map.insert("second".to_string(), vec![2]);
+
+        let (k, v) = map.get_key_value_mut("second").unwrap();
+        assert_eq!(k, "second");
+        v.push(k.len() as u32);
+
+        assert_eq!(map["second"], vec![2, 6]);
+        assert_eq!(map["first"], vec![1]);
+        assert!(map.get_key_value_mut("third").is_none());
+    }
+
+    #[test]
+    fn test_map_entry_remove_entry() {
+        let mut map: Map<String, u32> = Map::new(None);
+        map.insert("a".to_string(), 1);
+        map.insert("b".to_string(), 2);
+
+        let (k, v) = match map.entry("a".to_string()) {
+            Entry::Occupied(entry) => entry.remove_entry(),
+            Entry::Vacant(_) => panic!("key `a` should be occupied"),
+        };
+        assert_eq!((k.as_str(), v), ("a", 1));
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key("a"));
+        assert_eq!(map["b"], 2);
+    }
+
+    #[test]
+    fn test_map_try_reserve() {
+        let mut map = Map::new(None);
+        map.insert(1, 1);
+        assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.try_reserve(usize::MAX / 4), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map[&1], 1);
+
+        assert_eq!(map.try_reserve(100), Ok(()));
+        let buckets = map.bucket_count();
+        assert!(buckets * 3 / 4 >= 101);
+        for i in 2..=101 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.bucket_count(), buckets);
+        assert_eq!(map[&101], 101);
+    }
+
+    #[test]
+    fn test_map_shrink_to_fit_reuses_buckets() {
+        let mut map = Map::new(None);
+        for i in 0..200 {
+            map.insert(i, i);
+        }
+        for i in 10..200 {
+            map.remove(&i);
+        }
+        let mut old_caps = map.buckets.iter().map(Vec::capacity).collect::<Vec<_>>();
+        old_caps.sort_unstable_by(|a, b| b.cmp(a));
+
+        map.shrink_to_fit();
+        assert!(map.bucket_count() < old_caps.len());
+        assert_eq!(map.len(), 10);
+        assert!((0..10).all(|i| map[&i] == i));
+
+        // every kept bucket still has one of the biggest old allocations
+        let mut new_caps = map.buckets.iter().map(Vec::capacity).collect::<Vec<_>>();
+        new_caps.sort_unstable_by(|a, b| b.cmp(a));
+        assert!(new_caps.iter().zip(old_caps.iter()).all(|(new, old)| new >= old));
+        assert!(new_caps.iter().all(|&cap| cap > 0));
+    }
+
+    #[test]
+    fn test_map_entry_ref_hit_never_makes_key() {
+        #[derive(PartialEq, Eq, Hash)]
+        struct Key(String);
+        impl Borrow<str> for Key {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+        impl From<&str> for Key {
+            fn from(_: &str) -> Self {
+                panic!("entry_ref hit must not build a key")
+            }
+        }
+
+        let mut map = Map::new(None);
+        map.insert(Key("hit".to_string()), 1);
+        *map.entry_ref("hit").or_insert(0) += 1;
+        *map.entry_ref("hit").or_insert_with(|| 0) += 1;
+        assert_eq!(map["hit"], 3);
+        assert_eq!(map.len(), 1);
+
+        let mut map: Map<String, u32> = Map::new(None);
+        *map.entry_ref("miss").or_insert(5) += 1;
+        assert_eq!(map["miss"], 6);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_map_sum_values() {
+        let mut map: Map<&str, u64> = Map::new(None);
+        assert_eq!(map.sum_values(), 0);
+
+        map.insert("requests", 120);
+        map.insert("errors", 3);
+        map.insert("retries", 7);
+        assert_eq!(map.sum_values(), 130);
+        assert_eq!(map.values().copied().sum::<u64>(), 130);
+        assert_eq!(map.keys().count(), 3);
+    }
+
+    #[test]
+    fn test_map_or_insert_with_resizes_after_closure() {
+        let mut map = Map::new(Some(4));
+        for i in 0..3 {
+            map.insert(i, i);
+        }
+        let buckets = map.bucket_count();
+
+        // the map is full so the vacant insert resizes after `f` runs
+        let val = map.entry(100).or_insert_with(|| {
+            let mut other = Map::new(None);
+            other.insert(1, 99);
+            other[&1]
+        });
+        assert_eq!(*val, 99);
+        assert!(map.bucket_count() > buckets);
+        assert_eq!(map[&100], 99);
+        assert!((0..3).all(|i| map[&i] == i));
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn test_map_insert_if_absent() {
+        let mut map = Map::new(None);
+        assert_eq!(map.insert_if_absent("a".to_string(), vec![1]), Ok(()));
+        assert_eq!(map.insert_if_absent("b".to_string(), vec![2]), Ok(()));
+
+        let (k, v) = map.insert_if_absent("a".to_string(), vec![3]).unwrap_err();
+        assert_eq!((k.as_str(), v), ("a", vec![3]));
+        assert_eq!(map["a"], vec![1]);
+        assert_eq!(map.len(), 2);
+
+        for i in 0..50 {
+            assert!(map.insert_if_absent(i.to_string(), vec![i]).is_ok());
+        }
+        assert_eq!(map.len(), 52);
+        assert!((0..50).all(|i| map[i.to_string().as_str()] == vec![i]));
+    }
+
+    #[test]
+    fn test_map_with_capacity_never_resizes() {
+        for &n in [1, 5, 12, 100].iter() {
+            let mut map = Map::with_capacity(n);
+            let buckets = map.bucket_count();
+            assert!(buckets.is_power_of_two());
+            for i in 0..n {
+                map.insert(i, i);
+            }
+            assert_eq!(map.bucket_count(), buckets);
+        }
+
+        // 12 keys fill 16 buckets exactly to the 3/4 load
+        let mut map = Map::with_capacity(12);
+        for i in 0..12 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.bucket_count(), 16);
+        map.insert(12, 12);
+        assert_eq!(map.bucket_count(), 32);
+
+        assert_eq!(Map::<u8, u8>::with_capacity(0).bucket_count(), 0);
+    }
+
+    #[test]
+    fn test_map_retain() {
+        let mut map = Map::new(None);
+        for i in 0..30 {
+            map.insert(i, i);
+        }
+        map.retain(|k, v| {
+            *v *= 2;
+            k % 3 == 0
+        });
+        assert_eq!(map.len(), 10);
+        assert!((0..30).all(|k| map.get(&k).copied() == Some(k * 2).filter(|_| k % 3 == 0)));
+    }
+
+    #[test]
+    fn test_map_retain_stable_keeps_order() {
+        let mut map = Map::new(Some(2));
+        for i in 0..40 {
+            map.insert(i, i);
+        }
+        let expected = map.keys().copied().filter(|k| k % 4 != 1).collect::<Vec<_>>();
+
+        map.retain_stable(|k, _| k % 4 != 1);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), expected);
+        assert_eq!(map.len(), 30);
+    }
+
+    #[test]
+    fn test_map_get_in() {
+        let mut map: Map<&str, Map<u32, char>> = Map::new(None);
+        let mut inner = Map::new(None);
+        inner.insert(1, 'a');
+        map.insert("present", inner);
+        map.insert("empty", Map::new(None));
+
+        assert_eq!(map.get_in(&"present", &1), Some(&'a'));
+        assert_eq!(map.get_in(&"present", &2), None);
+        assert_eq!(map.get_in(&"empty", &1), None);
+        assert_eq!(map.get_in(&"missing", &1), None);
+
+        *map.get_in_mut(&"present", &1).unwrap() = 'b';
+        assert_eq!(map["present"][&1], 'b');
+        assert!(map.get_in_mut(&"missing", &1).is_none());
+    }
+
+    #[test]
+    fn test_map_bucket_for() {
+        let mut map = Map::new(Some(8));
+        map.insert("key", 1);
+        let other: Map<&str, u32> = Map::new(None);
+        assert_eq!(map.hash_of("key"), other.hash_of("key"));
+
+        let bucket = map.bucket_for("key");
+        assert!(bucket < map.bucket_count());
+        assert_eq!(bucket as u64, map.hash_of("key") % 8);
+        assert!(map.buckets[bucket].iter().any(|(k, _)| *k == "key"));
+    }
+
+    #[test]
+    fn test_map_clear_keeping_capacity() {
+        let mut map = Map::new(None);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let buckets = map.bucket_count();
+        let outer = map.buckets.as_ptr();
+        let inner = map.buckets.iter().map(|b| (b.as_ptr(), b.capacity())).collect::<Vec<_>>();
+
+        map.clear_keeping_capacity();
+        assert_eq!(map.len(), 0);
+        assert!(map.get(&1).is_none());
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.bucket_count(), buckets);
+        assert_eq!(map.buckets.as_ptr(), outer);
+        assert_eq!(map.buckets.iter().map(|b| (b.as_ptr(), b.capacity())).collect::<Vec<_>>(), inner);
+        assert_eq!(map[&99], 99);
+    }
+
+    #[test]
+    fn test_map_entry_and_remove_if() {
+        let mut map = Map::new(None);
+        map.insert("stale", 0);
+        map.insert("fresh", 5);
+
+        let entry = map.entry("stale").and_remove_if(|v| *v == 0);
+        assert!(matches!(entry, Entry::Vacant(_)));
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key("stale"));
+
+        *map.entry("fresh").and_remove_if(|v| *v == 0).or_insert(1) += 1;
+        assert_eq!(map["fresh"], 6);
+        assert_eq!(map.len(), 1);
+
+        // removed then inserted again in the same chain
+        assert_eq!(*map.entry("fresh").and_remove_if(|v| *v == 6).or_insert(1), 1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.items, 1);
+    }
+
+    #[test]
+    fn test_map_bucket_count_power_of_two() {
+        let mut map = Map::new(Some(3));
+        for i in 0..500 {
+            map.insert(i, i);
+            assert!(map.bucket_count().is_power_of_two(), "{} buckets", map.bucket_count());
+        }
+
+        map.reserve(1000);
+        let buckets = map.bucket_count();
+        assert!(buckets.is_power_of_two());
+        for i in 500..1500 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.bucket_count(), buckets);
+
+        map.retain(|k, _| *k < 10);
+        map.shrink_to_fit();
+        assert_eq!(map.bucket_count(), 16);
+        assert!((0..10).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    fn test_map_into_iter_deterministic() {
+        let build = || {
+            let mut map = Map::new(Some(4));
+            for i in 0..50 {
+                map.insert(i.to_string(), i);
+            }
+            map.remove("7");
+            map
+        };
+
+        let map = build();
+        let borrowed = map.iter().map(|(k, v)| (k.clone(), *v)).collect::<Vec<_>>();
+        let owned = map.into_iter().collect::<Vec<_>>();
+        assert_eq!(owned, borrowed);
+        assert_eq!(owned.len(), 49);
+        assert_eq!(build().into_iter().collect::<Vec<_>>(), owned);
+    }
+
+    #[test]
+    fn test_map_entries_reserves_once() {
+        let words = "a b c a b a d e f g h a".split(' ').collect::<Vec<_>>();
+
+        let mut map = Map::new(None);
+        map.entries(words.iter().copied(), |e| *e.or_insert(0) += 1);
+        let buckets = map.bucket_count();
+        assert_eq!(map.get("a"), Some(&4));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.len(), 8);
+
+        let mut naive = Map::new(None);
+        for w in words.iter().copied() {
+            *naive.entry(w).or_insert(0) += 1;
+        }
+        assert_eq!(naive.len(), map.len());
+        for (k, v) in naive.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+        // only the up front reservation was made
+        assert_eq!(buckets, Map::<&str, i32>::with_capacity(words.len()).bucket_count());
+    }
+
+    #[test]
+    fn test_map_entries_vs_naive_loop() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        // counts every key hashed, a resize hashes each entry it moves again
+        #[derive(Clone, Default)]
+        struct Counting(Rc<Cell<usize>>);
+        impl BuildHasher for Counting {
+            type Hasher = FnvHasher;
+            fn build_hasher(&self) -> FnvHasher {
+                self.0.set(self.0.get() + 1);
+                FnvHasher::default()
+            }
+        }
+
+        let words = (0..100).map(|i| i % 40).collect::<Vec<_>>();
+
+        let hashes = Counting::default();
+        let mut map = Map::with_hasher(None, hashes.clone());
+        map.entries(words.iter().copied(), |e| *e.or_insert(0) += 1);
+        let reserved = hashes.0.get();
+
+        let hashes = Counting::default();
+        let mut naive = Map::with_hasher(None, hashes.clone());
+        for w in words.iter().copied() {
+            *naive.entry(w).or_insert(0) += 1;
+        }
+        let grown = hashes.0.get();
+
+        assert_eq!(map.len(), naive.len());
+        // one hash per lookup, nothing was rehashed
+        assert_eq!(reserved, words.len());
+        assert!(grown > reserved, "{} <= {}", grown, reserved);
+    }
+
+    #[test]
+    fn test_map_items_invariant() {
+        let mut map = Map::new(Some(2));
+        // replacing used to count the key twice
+        for _ in 0..3 {
+            map.insert("a", 1);
+        }
+        assert_eq!(map.len(), 1);
+        // entry inserts used to never count
+        for k in &["b", "c", "d", "e"] {
+            *map.entry(k).or_insert(0) += 1;
+        }
+        *map.entry("b").or_insert(0) += 1;
+        assert_eq!(map.len(), 5);
+        if let Entry::Occupied(e) = map.entry("c") {
+            e.remove();
+        }
+        map.remove("a");
+        map.remove("missing");
+        assert_eq!(map.len(), 3);
+        map.check_items();
+    }
+
+    #[test]
+    fn test_map_default_in_derive() {
+        #[derive(Default)]
+        struct Index {
+            words: Map<String, usize>,
+        }
+
+        let mut index = Index::default();
+        assert!(index.words.is_empty());
+        assert_eq!(index.words.bucket_count(), 0);
+        index.words.insert("a".to_string(), 1);
+        assert_eq!(index.words.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn test_map_rehash_with() {
+        #[derive(Clone, Default)]
+        struct Seeded(u64);
+        impl BuildHasher for Seeded {
+            type Hasher = FnvHasher;
+            fn build_hasher(&self) -> FnvHasher {
+                FnvHasher::with_key(self.0)
+            }
+        }
+
+        let mut map = Map::with_hasher(None, Seeded(1));
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
+        let before = (0..100).map(|i| map.bucket_for(&i)).collect::<Vec<_>>();
+        let buckets = map.bucket_count();
+
+        map.rehash_with(Seeded(0xdead_beef));
+        let after = (0..100).map(|i| map.bucket_for(&i)).collect::<Vec<_>>();
+        assert_ne!(before, after);
+        assert_eq!(map.bucket_count(), buckets);
+        assert_eq!(map.len(), 100);
+        for i in 0..100 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+    }
+
     #[test]
     fn test_map_big() {
         let mut map = Map::new(None);
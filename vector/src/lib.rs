@@ -1,31 +1,134 @@
-#![feature(ptr_internals, allocator_api, alloc_layout_extra)]
-
-use std::alloc::{ Alloc, GlobalAlloc, Layout, Global, handle_alloc_error };
+use std::alloc::{handle_alloc_error, Layout};
+use std::cmp::Ordering;
 use std::fmt;
-use std::mem;
-use std::ops::{Deref, DerefMut};
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::{self, MaybeUninit};
+use std::ops::{
+    Bound, Deref, DerefMut, Index, IndexMut, Range, RangeBounds, RangeFrom, RangeFull,
+    RangeInclusive, RangeTo, RangeToInclusive,
+};
 use std::marker::PhantomData;
-use std::ptr::{Unique, NonNull, self};
+use std::ptr::{self, NonNull};
+
+/// A source of memory for a `Vector`, shaped like the old unstable
+/// `std::alloc::Alloc` so allocators written against that port over as is.
+///
+/// # Safety
+/// A block from `alloc` or `realloc` must fit the layout asked for and stay
+/// valid until it is handed back to `dealloc` or `realloc` with that same
+/// layout. Clones of an allocator must be able to free each other's blocks.
+pub unsafe trait Alloc {
+    /// Allocates a block for `layout`.
+    ///
+    /// # Safety
+    /// `layout` must have a non zero size.
+    unsafe fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocErr>;
+
+    /// Frees `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must be a live block of this allocator allocated with `layout`.
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Moves the block at `ptr` to one of `new_size` bytes, the default
+    /// allocates, copies and frees.
+    ///
+    /// # Safety
+    /// `ptr` must be a live block of this allocator allocated with `layout`
+    /// and `new_size` must be non zero. On success `ptr` is no longer valid.
+    unsafe fn realloc(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, AllocErr> {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new = self.alloc(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr(), layout.size().min(new_size));
+        self.dealloc(ptr, layout);
+        Ok(new)
+    }
+}
+
+/// The allocator could not hand out a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllocErr;
+
+/// The global allocator, used unless a `Vector` is given another.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
 
-#[derive(Clone)]
-struct RawVec<T> {
-    ptr: Unique<T>,
+unsafe impl Alloc for Global {
+    unsafe fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocErr> {
+        NonNull::new(std::alloc::alloc(layout)).ok_or(AllocErr)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        std::alloc::dealloc(ptr.as_ptr(), layout)
+    }
+
+    unsafe fn realloc(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, AllocErr> {
+        NonNull::new(std::alloc::realloc(ptr.as_ptr(), layout, new_size)).ok_or(AllocErr)
+    }
+}
+
+struct RawVec<T, A: Alloc = Global> {
+    ptr: NonNull<T>,
     cap: usize,
+    alloc: A,
+    growth: GrowthStrategy,
+    // owns the `T`s behind `ptr`, for drop check
+    _owns: PhantomData<T>,
+}
+
+// the buffer owns its `T`s like a `Box<[T]>` would, so `Vector` is as
+// thread safe as `T` and the allocator are
+unsafe impl<T: Send, A: Alloc + Send> Send for RawVec<T, A> {}
+unsafe impl<T: Sync, A: Alloc + Sync> Sync for RawVec<T, A> {}
+
+/// How much a full buffer grows by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowthStrategy {
+    /// Double the capacity, the default.
+    Double,
+    /// Grow the capacity by half, trading more reallocations for less
+    /// unused memory.
+    OnePointFive,
+}
+
+impl GrowthStrategy {
+    // the capacity after one step from `cap`, clamped to `max` and `None`
+    // once `cap` is already at `max`
+    fn grow_cap(self, cap: usize, max: usize) -> Option<usize> {
+        if cap >= max {
+            return None;
+        }
+        let grown = match self {
+            GrowthStrategy::Double => cap.saturating_mul(2),
+            GrowthStrategy::OnePointFive => cap.saturating_add(cap / 2),
+        };
+        Some(grown.max(cap + 1).min(max))
+    }
 }
 
-impl<T> Drop for RawVec<T> {
+impl<T, A: Alloc> Drop for RawVec<T, A> {
     fn drop(&mut self) {
         let item_size = mem::size_of::<T>();
         if self.cap != 0 && item_size != 0 {
             unsafe {
-                let c: NonNull<T> = self.ptr.into();
-                Global.dealloc(c.cast(), Layout::array::<T>(self.cap).unwrap())
+                self.alloc.dealloc(self.ptr.cast(), Self::layout(self.cap))
             }
         }
     }
 }
 
-impl<T> fmt::Debug for RawVec<T>
+impl<T, A: Alloc> fmt::Debug for RawVec<T, A>
 where
     T: fmt::Debug,
 {
@@ -35,71 +138,368 @@ where
 }
 
 impl<T> RawVec<T> {
-
     fn new() -> Self {
+        RawVec::new_in(Global)
+    }
+
+    fn with_capacity(cap: usize) -> Self {
+        RawVec::with_capacity_in(cap, Global)
+    }
+}
+
+impl<T, A: Alloc> RawVec<T, A> {
+
+    fn new_in(alloc: A) -> Self {
         let size_of = mem::size_of::<T>();
         let cap = if size_of == 0 { !0 } else { 0 };
-        RawVec { ptr: Unique::empty(), cap, }
+        RawVec {
+            ptr: NonNull::dangling(),
+            cap,
+            alloc,
+            growth: GrowthStrategy::Double,
+            _owns: PhantomData,
+        }
+    }
+
+    // the layout a buffer of `cap` elements is allocated with, every alloc,
+    // realloc and dealloc goes through here so they always agree, padding
+    // for over-aligned `T` included
+    fn layout(cap: usize) -> Layout {
+        Layout::array::<T>(cap).unwrap()
     }
+
+    // the most elements that fit in `isize::MAX` bytes
+    fn max_cap() -> usize {
+        isize::MAX as usize / mem::size_of::<T>().max(1)
+    }
+
+    // the capacity a reserve of `needed` grows to, following the growth
+    // strategy when that is more than `needed`
+    fn amortized_cap(&self, needed: usize) -> usize {
+        let max = Self::max_cap();
+        needed.max(self.growth.grow_cap(self.cap, max).unwrap_or(max))
+    }
+
     fn grow(&mut self) {
+        // a ZST buffer starts out with `usize::MAX` capacity
+        assert!(mem::size_of::<T>() != 0, "capacity overflow");
+        let new_cap = self.growth.grow_cap(self.cap, Self::max_cap()).expect("capacity overflow");
+        let new_layout = Self::layout(new_cap);
         unsafe {
-            let align = mem::align_of::<T>();
-            let item_size = mem::size_of::<T>();
-            println!("align: {} size: {} cap: {} ptr: {:?}", align, item_size, self.cap, self.ptr);
-
-            let (new_cap, ptr) = if self.cap == 0 {
-                let ptr = Global.alloc(Layout::array::<T>(1).unwrap());
-                (1, ptr)
+            let ptr = if self.cap == 0 {
+                self.alloc.alloc(new_layout)
             } else {
-                let new_cap = self.cap * 2;
-                let c: NonNull<T> = self.ptr.into();
-                let ptr = Global.realloc(
-                    c.cast(),
-                    Layout::array::<T>(self.cap).unwrap(),
-                    Layout::array::<T>(new_cap).unwrap().size()
-                );
-
-                (new_cap, ptr)
+                self.alloc.realloc(self.ptr.cast(), Self::layout(self.cap), new_layout.size())
             };
 
-            if ptr.is_err() {
-                handle_alloc_error(Layout::from_size_align_unchecked(
-                    new_cap * item_size,
-                    align
-                ))
+            match ptr {
+                Ok(ptr) => self.ptr = ptr.cast(),
+                Err(_) => handle_alloc_error(new_layout),
             }
-
-            let ptr = ptr.unwrap();
-            self.ptr = Unique::new_unchecked(ptr.as_ptr() as *mut _);
             self.cap = new_cap;
         }
-        println!("new cap: {}", self.cap)
     }
+
+    // one allocation of exactly `cap` elements, none for `cap == 0` or a ZST
+    fn with_capacity_in(cap: usize, mut alloc: A) -> Self {
+        if cap == 0 || mem::size_of::<T>() == 0 {
+            return RawVec::new_in(alloc);
+        }
+        let layout = Layout::array::<T>(cap).expect("capacity overflow");
+        unsafe {
+            match alloc.alloc(layout) {
+                Ok(ptr) => RawVec {
+                    ptr: ptr.cast(),
+                    cap,
+                    alloc,
+                    growth: GrowthStrategy::Double,
+                    _owns: PhantomData,
+                },
+                Err(_) => handle_alloc_error(layout),
+            }
+        }
+    }
+
+    // realloc down to `new_cap`, freeing the buffer for 0, callers make
+    // sure no live element is past `new_cap`
+    fn shrink_to(&mut self, new_cap: usize) {
+        if mem::size_of::<T>() == 0 || new_cap >= self.cap {
+            return;
+        }
+        if new_cap == 0 {
+            unsafe {
+                self.alloc.dealloc(self.ptr.cast(), Self::layout(self.cap));
+            }
+            self.ptr = NonNull::dangling();
+            self.cap = 0;
+            return;
+        }
+        let new_layout = Self::layout(new_cap);
+        unsafe {
+            match self.alloc.realloc(self.ptr.cast(), Self::layout(self.cap), new_layout.size()) {
+                Ok(ptr) => self.ptr = ptr.cast(),
+                Err(_) => handle_alloc_error(new_layout),
+            }
+        }
+        self.cap = new_cap;
+    }
+
+    // a fresh buffer with the same capacity, nothing is copied into it
+    fn alloc_same_cap(&self) -> Self
+    where
+        A: Clone,
+    {
+        let mut buff = RawVec::with_capacity_in(self.cap, self.alloc.clone());
+        buff.growth = self.growth;
+        buff
+    }
+
+    // grow to exactly `new_cap`, reporting failure instead of aborting
+    fn try_grow_to(&mut self, new_cap: usize) -> Result<(), TryReserveError> {
+        if new_cap <= self.cap {
+            return Ok(());
+        }
+        let new_layout = Layout::array::<T>(new_cap)
+            .ok()
+            .filter(|layout| layout.size() <= isize::MAX as usize)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        unsafe {
+            let ptr = if self.cap == 0 {
+                self.alloc.alloc(new_layout)
+            } else {
+                self.alloc.realloc(self.ptr.cast(), Self::layout(self.cap), new_layout.size())
+            };
+            let ptr = ptr.map_err(|_| TryReserveError::AllocError)?;
+            self.ptr = ptr.cast();
+        }
+        self.cap = new_cap;
+        Ok(())
+    }
+}
+
+/// Why a fallible reserve failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity overflows `usize` or `isize::MAX` bytes.
+    CapacityOverflow,
+    /// The allocator could not provide the memory.
+    AllocError,
 }
 
-#[derive(Clone)]
-pub struct Vector<T> {
-    buff: RawVec<T>,
+pub struct Vector<T, A: Alloc = Global> {
+    buff: RawVec<T, A>,
     len: usize,
 }
 
 impl<T> Vector<T> {
     pub fn new() -> Self {
-        assert!(mem::size_of::<T>() != 0, "we ain't ready fo dat");
         Self { buff: RawVec::new(), len: 0, }
     }
 
+    /// An empty vector that holds `cap` elements before reallocating.
+    pub fn with_capacity(cap: usize) -> Self {
+        Self { buff: RawVec::with_capacity(cap), len: 0, }
+    }
+
+    /// An empty vector whose buffer grows by `growth` when full.
+    pub fn with_growth(growth: GrowthStrategy) -> Self {
+        let mut vec = Self::new();
+        vec.buff.growth = growth;
+        vec
+    }
+
+    /// Rebuilds a vector from the parts of one taken apart with `as_mut_ptr`,
+    /// `len` and `capacity`, or of a `Vec`.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by the global allocator for `cap`
+    /// elements of `T` (or be dangling when nothing was allocated) and the
+    /// first `len` elements must be initialized. The vector takes ownership
+    /// of the allocation.
+    pub unsafe fn from_raw_parts(ptr: *mut T, len: usize, cap: usize) -> Self {
+        let buff = RawVec {
+            ptr: NonNull::new_unchecked(ptr),
+            cap,
+            alloc: Global,
+            growth: GrowthStrategy::Double,
+            _owns: PhantomData,
+        };
+        Vector { buff, len, }
+    }
+
+    /// Drops the spare capacity and hands the buffer over as a boxed slice,
+    /// a vector already at `len == capacity` is not reallocated.
+    pub fn into_boxed_slice(mut self) -> Box<[T]> {
+        self.shrink_to_fit();
+        let ptr = self.ptr();
+        let len = self.len;
+        mem::forget(self);
+        unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)) }
+    }
+
+    /// Gives up ownership of the elements, the spare capacity is freed first.
+    pub fn leak<'a>(self) -> &'a mut [T] {
+        Box::leak(self.into_boxed_slice())
+    }
+
+    /// A vector of `n` clones of `elem`, backs `vector![elem; n]`.
+    pub fn from_elem(elem: T, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        let mut vec = Self::with_capacity(n);
+        vec.resize(n, elem);
+        vec
+    }
+}
+
+impl<T, A: Alloc> Vector<T, A> {
+    /// An empty vector that gets its buffer from `alloc`.
+    pub fn new_in(alloc: A) -> Self {
+        Self { buff: RawVec::new_in(alloc), len: 0, }
+    }
+
+    /// An empty vector that holds `cap` elements before reallocating, the
+    /// buffer comes from `alloc`.
+    pub fn with_capacity_in(cap: usize, alloc: A) -> Self {
+        Self { buff: RawVec::with_capacity_in(cap, alloc), len: 0, }
+    }
+
+    /// How many elements fit before the buffer has to grow.
+    pub fn capacity(&self) -> usize { self.buff.cap }
+
+    /// The number of elements in the vector.
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// The uninitialized slots between `len` and `capacity`, fill them and
+    /// then `set_len` to make them part of the vector.
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.ptr().add(self.len) as *mut MaybeUninit<T>,
+                self.cap() - self.len,
+            )
+        }
+    }
+
+    /// Sets the length without touching the elements.
+    ///
+    /// # Safety
+    /// `new_len` must be at most `capacity` and the elements in `0..new_len`
+    /// must be initialized. Elements past `new_len` are not dropped.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.cap());
+        self.len = new_len;
+    }
+
+    /// A pointer to the buffer, dangling while nothing is allocated.
+    pub fn as_ptr(&self) -> *const T { self.ptr() }
+
+    /// A mutable pointer to the buffer, dangling while nothing is allocated.
+    pub fn as_mut_ptr(&mut self) -> *mut T { self.ptr() }
+
+    /// Iterates over references to the elements, front to back.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        unsafe { std::slice::from_raw_parts(self.ptr(), self.len).iter() }
+    }
+
+    /// Iterates over mutable references to the elements, front to back.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr(), self.len).iter_mut() }
+    }
+
     fn cap(&self) -> usize { self.buff.cap }
 
     fn ptr(&self) -> *mut T { self.buff.ptr.as_ptr() }
 
+    // resolve `range` to `start..end` asserting it lies within `0..len`
+    fn bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.checked_add(1).expect("Vector range start overflows usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.checked_add(1).expect("Vector range end overflows usize"),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len,
+        };
+        assert!(start <= end, "Vector range start {} greater than end {}", start, end);
+        assert!(end <= self.len, "Vector range end {} out of bounds {}", end, self.len);
+        (start, end)
+    }
+
+    /// Grows the buffer so `additional` more elements fit, at least doubling
+    /// the capacity so repeated reserves stay amortized O(1).
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed > self.cap() {
+            self.reserve_to(self.buff.amortized_cap(needed));
+        }
+    }
+
+    /// Grows the buffer to hold exactly `additional` more elements.
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        self.reserve_to(needed);
+    }
+
+    // like `try_grow_to` but panics on overflow and aborts on allocation
+    // failure the same as `grow`
+    fn reserve_to(&mut self, new_cap: usize) {
+        match self.buff.try_grow_to(new_cap) {
+            Ok(()) => {}
+            Err(TryReserveError::CapacityOverflow) => panic!("capacity overflow"),
+            Err(TryReserveError::AllocError) => {
+                handle_alloc_error(RawVec::<T, A>::layout(new_cap))
+            }
+        }
+    }
+
+    /// Like `reserve` but returns an error instead of panicking or aborting
+    /// when the capacity overflows or the allocator fails.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self.len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+        if needed > self.cap() {
+            self.buff.try_grow_to(self.buff.amortized_cap(needed))?;
+        }
+        Ok(())
+    }
+
+    /// Grows the buffer to hold exactly `additional` more elements, returning
+    /// an error instead of aborting if that is impossible.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self.len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+        self.buff.try_grow_to(needed)
+    }
+
     pub fn push(&mut self, item: T) {
         if self.len == self.cap() { self.buff.grow() };
 
         unsafe {
-            ptr::write(self.ptr().offset(self.len as isize), item);
+            ptr::write(self.ptr().add(self.len), item);
+        }
+        self.len += 1;
+    }
+
+    /// Pushes `item` only if it fits without growing, otherwise hands it
+    /// back. Never allocates.
+    pub fn push_within_capacity(&mut self, item: T) -> Result<(), T> {
+        if self.len == self.cap() {
+            return Err(item);
+        }
+        unsafe {
+            ptr::write(self.ptr().add(self.len), item);
         }
         self.len += 1;
+        Ok(())
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -108,13 +508,87 @@ impl<T> Vector<T> {
         } else {
             self.len -= 1;
             unsafe {
-                Some(ptr::read(self.ptr().offset(self.len as isize)))
+                Some(ptr::read(self.ptr().add(self.len)))
+            }
+        }
+    }
+
+    /// Pops the last element only if `f` returns true for it, otherwise the
+    /// vector is left as is.
+    pub fn pop_if<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) -> Option<T> {
+        let last = self.last_mut()?;
+        if f(last) { self.pop() } else { None }
+    }
+
+    /// Removes and returns the element at `idx`, moving the last element into
+    /// its place. This is O(1) but does not keep the order.
+    pub fn swap_remove(&mut self, idx: usize) -> T {
+        assert!(idx < self.len, "swap_remove index {} out of bounds {}", idx, self.len);
+        self.len -= 1;
+        unsafe {
+            let res = ptr::read(self.ptr().add(idx));
+            if idx != self.len {
+                ptr::copy(self.ptr().add(self.len), self.ptr().add(idx), 1);
+            }
+            res
+        }
+    }
+
+    /// Removes and returns the first element, moving the last element into
+    /// its place. This is O(1) but does not keep the order.
+    pub fn swap_remove_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            unsafe {
+                let res = ptr::read(self.ptr());
+                if self.len > 0 {
+                    // backfill the hole with the old last elem
+                    ptr::copy_nonoverlapping(self.ptr().add(self.len), self.ptr(), 1);
+                }
+                Some(res)
             }
         }
     }
 
+    /// Swaps the elements at `a` and `b`.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.len, "swap index {} out of bounds {}", a, self.len);
+        assert!(b < self.len, "swap index {} out of bounds {}", b, self.len);
+        unsafe { ptr::swap(self.ptr().add(a), self.ptr().add(b)) }
+    }
+
+    /// Reverses the order of the elements in place.
+    pub fn reverse(&mut self) {
+        self.reverse_range(0, self.len);
+    }
+
+    // reverse `start..end`, both already checked against `len`
+    fn reverse_range(&mut self, mut start: usize, mut end: usize) {
+        while start + 1 < end {
+            end -= 1;
+            unsafe { ptr::swap(self.ptr().add(start), self.ptr().add(end)) }
+            start += 1;
+        }
+    }
+
+    /// Rotates the elements so the one at `mid` becomes the first.
+    pub fn rotate_left(&mut self, mid: usize) {
+        assert!(mid <= self.len, "rotate index {} out of bounds {}", mid, self.len);
+        self.reverse_range(0, mid);
+        self.reverse_range(mid, self.len);
+        self.reverse_range(0, self.len);
+    }
+
+    /// Rotates the elements so the last `k` become the first.
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(k <= self.len, "rotate count {} out of bounds {}", k, self.len);
+        self.rotate_left(self.len - k);
+    }
+
     pub fn insert(&mut self, idx: usize, item: T) {
-        assert!(idx <= self.len, format!("index {} out of bounds {}", idx, self.len));
+        assert!(idx <= self.len, "index {} out of bounds {}", idx, self.len);
         // grow incase of at_cap
         if self.len == self.cap() { self.buff.grow() };
 
@@ -122,28 +596,68 @@ impl<T> Vector<T> {
             if idx < self.len {
                 // copy mem at idx and shift down one
                 ptr::copy(
-                    self.ptr().offset(idx as isize),
-                    self.ptr().offset((idx as isize) + 1),
+                    self.ptr().add(idx),
+                    self.ptr().add(idx + 1),
                     // shift this many elem over
                     self.len - idx,
                 );
             }
             // write new item to gap
-            ptr::write(self.ptr().offset(idx as isize), item);
+            ptr::write(self.ptr().add(idx), item);
             self.len += 1;
         }
     }
 
+    /// Inserts every item of `iter` starting at `idx`, shifting the tail
+    /// right once by the iterator's length.
+    pub fn insert_iter_at<I>(&mut self, idx: usize, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        assert!(idx <= self.len, "index {} out of bounds {}", idx, self.len);
+        let iter = iter.into_iter();
+        let count = iter.len();
+        self.reserve(count);
+
+        let tail = self.len - idx;
+        unsafe {
+            // open a gap of `count` elems at idx
+            ptr::copy(
+                self.ptr().add(idx),
+                self.ptr().add(idx + count),
+                tail,
+            );
+            // if `iter` panics the tail leaks instead of being dropped twice
+            self.len = idx;
+
+            let mut written = 0;
+            for item in iter.take(count) {
+                ptr::write(self.ptr().add(idx + written), item);
+                written += 1;
+            }
+            if written < count {
+                // the iterator lied about its length, close the rest of the gap
+                ptr::copy(
+                    self.ptr().add(idx + count),
+                    self.ptr().add(idx + written),
+                    tail,
+                );
+            }
+            self.len = idx + written + tail;
+        }
+    }
+
     pub fn remove(&mut self, idx: usize) -> T {
-        assert!(idx < self.len, format!("index {} out of bounds {}", idx, self.len));
+        assert!(idx < self.len, "index {} out of bounds {}", idx, self.len);
         unsafe {
             self.len -= 1;
-            let res = ptr::read(self.ptr().offset(idx as isize));
+            let res = ptr::read(self.ptr().add(idx));
             ptr::copy(
                 // start one past
-                self.ptr().offset((idx as isize) + 1),
+                self.ptr().add(idx + 1),
                 // smash removed offsets mem
-                self.ptr().offset(idx as isize),
+                self.ptr().add(idx),
                 // left shift this many
                 self.len - idx
             );
@@ -152,255 +666,2313 @@ impl<T> Vector<T> {
         }
     }
 
-    pub fn into_iter(self) -> IntoIter<T> 
-    where
-        T: fmt::Debug,
-    {    
+    /// Like `remove` but returns `None` when `idx` is out of bounds.
+    pub fn try_remove(&mut self, idx: usize) -> Option<T> {
+        if idx < self.len { Some(self.remove(idx)) } else { None }
+    }
+
+    /// Like `swap_remove` but returns `None` when `idx` is out of bounds.
+    pub fn try_swap_remove(&mut self, idx: usize) -> Option<T> {
+        if idx < self.len { Some(self.swap_remove(idx)) } else { None }
+    }
+
+    /// Drops every element past `len`, does nothing if there are no more
+    /// than `len` elements.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let tail = self.len - len;
+        // shorten first so a panicking drop can not leave dropped elements
+        // in the live range
+        self.len = len;
         unsafe {
-            let iter = RawIter::new(&self);
-            let buff = ptr::read(&self.buff);
-            println!("{:?}", buff);
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.ptr().add(len),
+                tail,
+            ));
+        }
+    }
 
-            mem::forget(self);
+    /// Grows to `new_len` with clones of `value`, moving `value` itself into
+    /// the last slot, or truncates to `new_len`.
+    pub fn resize(&mut self, new_len: usize, value: T)
+    where
+        T: Clone,
+    {
+        if new_len <= self.len {
+            self.truncate(new_len);
+            return;
+        }
+        self.reserve(new_len - self.len);
+        while self.len < new_len - 1 {
+            self.push(value.clone());
+        }
+        self.push(value);
+    }
 
-            IntoIter {
-                _buff: buff,
-                iter,
-            }
+    /// Grows to `new_len` filling the new slots with `f()`, or truncates to
+    /// `new_len`.
+    pub fn resize_with<F: FnMut() -> T>(&mut self, new_len: usize, mut f: F) {
+        if new_len <= self.len {
+            self.truncate(new_len);
+            return;
+        }
+        self.reserve(new_len - self.len);
+        while self.len < new_len {
+            self.push(f());
         }
     }
 
-    pub fn drain(&mut self) -> Drain<T> {
-        unsafe {
-            let iter = RawIter::new(&self);
+    /// Reallocates the buffer down to exactly `len` elements.
+    pub fn shrink_to_fit(&mut self) {
+        self.buff.shrink_to(self.len);
+    }
 
-            self.len = 0; 
+    /// Reallocates the buffer down to the larger of `len` and `min_capacity`,
+    /// a buffer already that small is left alone.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.buff.shrink_to(self.len.max(min_capacity));
+    }
 
-            Drain {
-                iter,
-                _vec: PhantomData
-            }
+    /// Drops every element keeping the buffer for reuse.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Drops every element and frees the buffer, leaving capacity at 0.
+    pub fn clear_and_shrink(&mut self) {
+        self.clear();
+        self.shrink_to_fit();
+    }
+
+    /// Overwrites the elements in `range` with clones of `value`, dropping
+    /// the old elements.
+    pub fn fill_range<R: RangeBounds<usize>>(&mut self, range: R, value: T)
+    where
+        T: Clone,
+    {
+        let (start, end) = self.bounds(range);
+        for slot in &mut self[start..end] {
+            *slot = value.clone();
         }
     }
-}
-impl<T> PartialEq for Vector<T>
-where
-    T: PartialEq
-{
-    fn eq(&self, other: &Self) -> bool {
-        self[..] == other[..]
+
+    /// Sorts the elements then removes consecutive duplicates, leaving a
+    /// sorted set.
+    pub fn sort_dedup(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort();
+        self.dedup();
     }
-}
-impl<T> Drop for Vector<T> {
-    fn drop(&mut self) {
-        while let Some(_) = self.pop() {}
+
+    /// Iterator from the back paired with each element's index, so it yields
+    /// `(len - 1, last)` down to `(0, first)`.
+    pub fn enumerate_rev(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.iter().enumerate().rev()
     }
-}
-impl<T> Deref for Vector<T> {
-    type Target = [T];
-    fn deref(&self) -> &Self::Target {
-        unsafe {
-            ::std::slice::from_raw_parts(self.ptr(), self.len)
+
+    /// Splits into owned chunks of `size` elements, the last chunk holds the
+    /// remainder. Elements are moved, never cloned.
+    pub fn into_chunks(mut self, size: usize) -> Vector<Vector<T>> {
+        assert!(size != 0, "chunk size must be non zero");
+        let mut chunks = Vector::new();
+        let mut chunk = Vector::new();
+        for item in self.drain(..) {
+            chunk.push(item);
+            if chunk.len() == size {
+                chunks.push(mem::take(&mut chunk));
+            }
         }
-    }
-}
-impl<T> DerefMut for Vector<T> {
-    fn deref_mut(&mut self) -> &mut [T] {
-        unsafe {
-            ::std::slice::from_raw_parts_mut(self.ptr(), self.len)
+        if !chunk.is_empty() {
+            chunks.push(chunk);
         }
+        chunks
     }
-}
-impl<T> fmt::Debug for Vector<T>
-where
-    T: fmt::Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "[ ")?;
-        for (idx, x) in self.iter().enumerate() {
-            if (self.len() - 1) == idx {
-                return write!(f, "{:?} ]", x)
+
+    /// Consumes the vector moving each maximal run of adjacent elements with
+    /// equal `key` into its own vector.
+    pub fn group_by_key<B, F>(mut self, mut key: F) -> Vector<Vector<T>>
+    where
+        B: PartialEq,
+        F: FnMut(&T) -> B,
+    {
+        let mut groups = Vector::new();
+        let mut group = Vector::new();
+        let mut group_key = None;
+        for item in self.drain(..) {
+            let k = key(&item);
+            if group_key.as_ref().is_some_and(|gk| *gk != k) {
+                groups.push(mem::take(&mut group));
             }
-            write!(f, "{:?}, ", x)?;
+            group_key = Some(k);
+            group.push(item);
         }
-        write!(f, "]")
+        if !group.is_empty() {
+            groups.push(group);
+        }
+        groups
     }
-}
 
-impl<T> From<Vec<T>> for Vector<T> {
-    fn from(mut vec: Vec<T>) -> Vector<T> {
-        let ptr = Unique::new(vec.as_mut_ptr());
-        let ptr = ptr.unwrap();
+    /// Running fold of `op` over the elements, element `i` of the result is
+    /// `op` applied over `self[0..=i]`.
+    pub fn cumulative<F>(&self, mut op: F) -> Vector<T>
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> T,
+    {
+        let mut res = Vector::with_capacity(self.len);
 
-        let cap = vec.capacity();
-        let len = vec.len();
+        let mut iter = self.iter();
+        if let Some(first) = iter.next() {
+            res.push(first.clone());
+            for item in iter {
+                let next = op(&res[res.len() - 1], item);
+                res.push(next);
+            }
+        }
+        res
+    }
 
-        mem::forget(vec);
+    /// Keeps only the elements for which `f` is true, in place and in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_inner(|_, item| f(item))
+    }
 
-        let buff = RawVec { ptr, cap, };
-        Vector { buff, len, }
+    /// Like `retain` but `f` may also change the elements it keeps.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        self.retain_inner(|_, item| f(item))
     }
-}
 
-impl<T> Into<Vec<T>> for Vector<T> {
-    fn into(self) -> Vec<T> {
-        let ptr = self.ptr();
-        let cap = self.cap();
-        let len = self.len();
+    /// Drops consecutive repeated elements, keeping the first of each run.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
 
-        mem::forget(self);
+    /// Drops consecutive elements that map to the same key, keeping the
+    /// first of each run.
+    pub fn dedup_by_key<K, F>(&mut self, mut key: F)
+    where
+        K: PartialEq,
+        F: FnMut(&mut T) -> K,
+    {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Drops each element for which `same(elem, last_kept)` is true, so the
+    /// first of every run of matching elements is kept.
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same: F) {
+        let base = self.ptr();
+        let mut kept = 0;
+        // kept elements are already compacted to the front when `f` runs,
+        // so the last one kept sits at `kept - 1`
+        self.retain_inner(|_, item| {
+            let keep = kept == 0 || !same(item, unsafe { &mut *base.add(kept - 1) });
+            if keep {
+                kept += 1;
+            }
+            keep
+        })
+    }
+
+    /// Keeps only the elements for which `f(idx, &elem)` is true, `idx` is
+    /// the element's position before anything was removed.
+    pub fn retain_indexed<F: FnMut(usize, &T) -> bool>(&mut self, mut f: F) {
+        self.retain_inner(|idx, item| f(idx, item))
+    }
+
+    // single pass compaction, if `f` or a drop panics the unchecked tail is
+    // shifted down so every remaining elem is dropped exactly once
+    fn retain_inner<F: FnMut(usize, &mut T) -> bool>(&mut self, mut f: F) {
+        struct Guard<'a, T, A: Alloc> {
+            vec: &'a mut Vector<T, A>,
+            processed: usize,
+            deleted: usize,
+            len: usize,
+        }
+        impl<'a, T, A: Alloc> Drop for Guard<'a, T, A> {
+            fn drop(&mut self) {
+                unsafe {
+                    if self.deleted > 0 {
+                        ptr::copy(
+                            self.vec.ptr().add(self.processed),
+                            self.vec.ptr().add(self.processed - self.deleted),
+                            self.len - self.processed,
+                        );
+                    }
+                }
+                self.vec.len = self.len - self.deleted;
+            }
+        }
+
+        let len = self.len;
+        // nothing is visible to the vector until the guard restores len
+        self.len = 0;
+        let mut g = Guard { vec: self, processed: 0, deleted: 0, len };
+        while g.processed < len {
+            unsafe {
+                let cur = g.vec.ptr().add(g.processed);
+                if !f(g.processed, &mut *cur) {
+                    g.processed += 1;
+                    g.deleted += 1;
+                    ptr::drop_in_place(cur);
+                    continue;
+                }
+                if g.deleted > 0 {
+                    ptr::copy_nonoverlapping(cur, cur.sub(g.deleted), 1);
+                }
+                g.processed += 1;
+            }
+        }
+    }
+
+    /// Removes and yields, lazily, the elements `f` returns true for. The
+    /// rest keep their order and are shifted down when the iterator drops,
+    /// elements not yet checked by then are kept. If the iterator is leaked
+    /// the vector is left empty.
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, f: F) -> ExtractIf<'_, T, F, A> {
+        let old_len = self.len;
+        // only the guard's drop makes the survivors visible again
+        self.len = 0;
+        ExtractIf { vec: self, idx: 0, deleted: 0, old_len, pred: f }
+    }
+
+    /// Run length encodes the elements into `(value, run_length)` pairs,
+    /// one per run of consecutive equal elements.
+    pub fn run_length_encode(&self) -> Vector<(T, usize)>
+    where
+        T: PartialEq + Clone,
+    {
+        // one pass to count the runs so the result is allocated exactly once
+        let runs = if self.len == 0 {
+            0
+        } else {
+            1 + self.iter().zip(self.iter().skip(1)).filter(|(a, b)| a != b).count()
+        };
+        let mut res: Vector<(T, usize)> = Vector::with_capacity(runs);
+        for item in self.iter() {
+            match res.last_mut() {
+                Some((last, count)) if last == item => *count += 1,
+                _ => res.push((item.clone(), 1)),
+            }
+        }
+        res
+    }
+
+    /// Consumes the vector returning `[0..mid]` and `[mid..]`, the first half
+    /// keeps the buffer and the elements are moved, never cloned.
+    pub fn split_into(self, mid: usize) -> (Vector<T, A>, Vector<T, A>)
+    where
+        A: Clone,
+    {
+        let mut head = self;
+        let tail = head.split_off(mid);
+        (head, tail)
+    }
+
+    /// Moves `[at..]` into a new vector sized to fit it, `self` keeps its
+    /// buffer and `[..at]`.
+    pub fn split_off(&mut self, at: usize) -> Vector<T, A>
+    where
+        A: Clone,
+    {
+        assert!(at <= self.len, "index {} out of bounds {}", at, self.len);
+        let count = self.len - at;
+        let mut tail = Vector::with_capacity_in(count, self.buff.alloc.clone());
+        tail.buff.growth = self.buff.growth;
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr().add(at), tail.ptr(), count);
+        }
+        self.len = at;
+        tail.len = count;
+        tail
+    }
+
+    /// Moves every element of `other` onto the end, `other` is left empty
+    /// but keeps its buffer.
+    pub fn append(&mut self, other: &mut Vector<T, A>) {
+        let count = other.len;
+        self.reserve(count);
+        unsafe {
+            ptr::copy_nonoverlapping(other.ptr(), self.ptr().add(self.len), count);
+        }
+        other.len = 0;
+        self.len += count;
+    }
+
+    /// Clones every element of `other` onto the end.
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve(other.len());
+        for item in other {
+            self.push(item.clone());
+        }
+    }
+
+    /// Clones the elements in `range` onto the end.
+    pub fn extend_from_within<R: RangeBounds<usize>>(&mut self, range: R)
+    where
+        T: Clone,
+    {
+        let (start, end) = self.bounds(range);
+        // grow up front so the source never moves while we clone from it
+        self.reserve(end - start);
+
+        for idx in start..end {
+            unsafe {
+                let item = (*self.ptr().add(idx)).clone();
+                ptr::write(self.ptr().add(self.len), item);
+            }
+            self.len += 1;
+        }
+    }
+
+    /// Sorts by `f`, calling it once per element and caching the keys.
+    pub fn sort_by_cached_key<B: Ord, F: FnMut(&T) -> B>(&mut self, f: F) {
+        self.deref_mut().sort_by_cached_key(f)
+    }
+
+    /// Removes and returns the first element equal to `item`, shifting the
+    /// tail down.
+    pub fn remove_item(&mut self, item: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let idx = self.iter().position(|x| x == item)?;
+        Some(self.remove(idx))
+    }
+
+    /// Removes and returns the first element equal to `item` by moving the
+    /// last element into its place.
+    pub fn swap_remove_item(&mut self, item: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let idx = self.iter().position(|x| x == item)?;
+        Some(self.swap_remove(idx))
+    }
+
+    /// Returns the first occurrence of each element in order.
+    pub fn unique(&self) -> Vector<T>
+    where
+        T: std::hash::Hash + Eq + Clone,
+    {
+        let mut seen = std::collections::HashSet::with_capacity(self.len);
+        let mut res = Vector::with_capacity(self.len);
+        for item in self.iter() {
+            if seen.insert(item) {
+                res.push(item.clone());
+            }
+        }
+        res
+    }
+
+    /// Returns a new vector with a clone of `sep` between each pair of
+    /// elements.
+    pub fn with_separator(&self, sep: T) -> Vector<T>
+    where
+        T: Clone,
+    {
+        if self.len == 0 {
+            return Vector::new();
+        }
+        let mut res = Vector::with_capacity(2 * self.len - 1);
+
+        let mut iter = self.iter();
+        res.push(iter.next().unwrap().clone());
+        for item in iter {
+            res.push(sep.clone());
+            res.push(item.clone());
+        }
+        res
+    }
+
+    /// Drops the elements in `range` and shifts the tail down to close the
+    /// gap.
+    pub fn remove_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        let (start, end) = self.bounds(range);
+        let len = self.len;
+        unsafe {
+            // if a drop panics the tail leaks instead of being dropped twice
+            self.len = start;
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.ptr().add(start),
+                end - start,
+            ));
+            ptr::copy(
+                self.ptr().add(end),
+                self.ptr().add(start),
+                len - end,
+            );
+        }
+        self.len = len - (end - start);
+    }
+
+    /// Splits the live elements into at most `n` disjoint slices whose
+    /// lengths differ by at most one, e.g. to hand each to its own thread.
+    pub fn chunks_mut_vec(&mut self, n: usize) -> Vec<&mut [T]> {
+        assert!(n > 0, "cannot split into 0 chunks");
+        let pieces = n.min(self.len);
+        let (base, extra) = self.len.checked_div(pieces).map_or((0, 0), |base| (base, self.len % pieces));
+
+        let mut chunks = Vec::with_capacity(pieces);
+        let mut rest = &mut self[..];
+        for i in 0..pieces {
+            let (chunk, tail) = rest.split_at_mut(base + if i < extra { 1 } else { 0 });
+            chunks.push(chunk);
+            rest = tail;
+        }
+        chunks
+    }
+
+    /// Moves the items of `iter` onto the end, when `self` has no buffer
+    /// `iter`'s buffer is reused, see `From<IntoIter>`.
+    pub fn extend_vector(&mut self, iter: IntoIter<T, A>) {
+        if self.len == 0 && self.cap() == 0 {
+            let growth = self.buff.growth;
+            *self = Vector::from(iter);
+            self.buff.growth = growth;
+        } else {
+            for item in iter {
+                self.push(item);
+            }
+        }
+    }
+
+    /// Removes and yields the elements in `range`, the tail is shifted down
+    /// to close the gap when the `Drain` drops, whether or not every element
+    /// was taken.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, A> {
+        let (start, end) = self.bounds(range);
+        let len = self.len;
+        unsafe {
+            let iter = RawIter::new(&self[start..end]);
+
+            // until the drop `len` only covers the elements before the range
+            self.len = start;
+
+            Drain {
+                vec: NonNull::from(self),
+                iter,
+                tail_start: end,
+                tail_len: len - end,
+                _vec: PhantomData
+            }
+        }
+    }
+}
+/// Element types with no padding for which every bit pattern is a valid
+/// value, so their memory can be viewed as plain bytes.
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, have no padding bytes and be valid for
+/// any bit pattern.
+pub unsafe trait Pod: Copy {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => { $(unsafe impl Pod for $t {})* };
+}
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl<T: Pod, A: Alloc> Vector<T, A> {
+    /// The live elements as bytes in native endianness, reading them back on
+    /// a machine of other endianness needs a byte swap per element.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.ptr() as *const u8,
+                self.len * mem::size_of::<T>(),
+            )
+        }
+    }
+}
+
+impl<T: Copy> Vector<T> {
+    /// Copies `bytes` into a new vector, the bytes need not be aligned for
+    /// `T` and are read in native endianness.
+    ///
+    /// Panics if `T` is zero sized or `bytes.len()` is not a multiple of
+    /// `size_of::<T>()`.
+    ///
+    /// # Safety
+    ///
+    /// Each `size_of::<T>()` chunk of `bytes` must be a valid `T`, this always
+    /// holds for [`Pod`] types.
+    // `usize::is_multiple_of` is newer than the compilers this crate supports
+    #[allow(clippy::manual_is_multiple_of)]
+    pub unsafe fn from_bytes_copy(bytes: &[u8]) -> Vector<T> {
+        let size = mem::size_of::<T>();
+        assert!(size != 0, "cannot read a zero sized type from bytes");
+        assert!(bytes.len() % size == 0, "{} bytes is not a multiple of {}", bytes.len(), size);
+        let len = bytes.len() / size;
+        let mut vec = Vector::with_capacity(len);
+        ptr::copy_nonoverlapping(bytes.as_ptr(), vec.ptr() as *mut u8, bytes.len());
+        vec.len = len;
+        vec
+    }
+}
+
+impl<V, A: Alloc> Vector<V, A> {
+    /// Flattens a vector of slices into one vector.
+    pub fn concat<T: Clone>(&self) -> Vector<T>
+    where
+        V: AsRef<[T]>,
+    {
+        let total = self.iter().map(|seg| seg.as_ref().len()).sum();
+        let mut res = Vector::with_capacity(total);
+        for seg in self.iter() {
+            res.extend_from_slice(seg.as_ref());
+        }
+        res
+    }
+
+    /// Flattens a vector of slices into one vector with `sep` between each.
+    pub fn join<T: Clone>(&self, sep: &T) -> Vector<T>
+    where
+        V: AsRef<[T]>,
+    {
+        let total: usize = self.iter().map(|seg| seg.as_ref().len()).sum();
+        let mut res = Vector::with_capacity(total + self.len.saturating_sub(1));
+        for (i, seg) in self.iter().enumerate() {
+            if i > 0 {
+                res.push(sep.clone());
+            }
+            res.extend_from_slice(seg.as_ref());
+        }
+        res
+    }
+}
+
+impl<A: Alloc> Vector<u8, A> {
+    /// One's complement internet checksum (RFC 1071) of the bytes, an odd
+    /// trailing byte is padded with a zero byte.
+    pub fn checksum_u16(&self) -> u16 {
+        let sum = self.chunks(2).fold(0_u32, |acc, word| {
+            let word = match word {
+                [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+                [hi] => u16::from_be_bytes([*hi, 0]),
+                _ => unreachable!("chunks(2) yields 1 or 2 bytes"),
+            };
+            // fold the carry back in every step so `acc` never overflows
+            let acc = acc + u32::from(word);
+            (acc & 0xffff) + (acc >> 16)
+        });
+        !(sum as u16)
+    }
+}
+
+impl<T, A: Alloc> PartialEq for Vector<T, A>
+where
+    T: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+impl<T: Eq, A: Alloc> Eq for Vector<T, A> {}
+
+// equality with the std sequence types, in both directions
+macro_rules! impl_slice_eq {
+    ([$($vars:tt)*] $lhs:ty, $rhs:ty) => {
+        impl<T, U, $($vars)*> PartialEq<$rhs> for $lhs
+        where
+            T: PartialEq<U>
+        {
+            fn eq(&self, other: &$rhs) -> bool {
+                self[..] == other[..]
+            }
+        }
+    };
+}
+impl_slice_eq!([A: Alloc] Vector<T, A>, Vec<U>);
+impl_slice_eq!([A: Alloc] Vec<T>, Vector<U, A>);
+impl_slice_eq!([A: Alloc] Vector<T, A>, &[U]);
+impl_slice_eq!([A: Alloc] &[T], Vector<U, A>);
+impl_slice_eq!([A: Alloc, const N: usize] Vector<T, A>, [U; N]);
+impl_slice_eq!([A: Alloc, const N: usize] [T; N], Vector<U, A>);
+
+impl<T: PartialOrd, A: Alloc> PartialOrd for Vector<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self[..].partial_cmp(&other[..])
+    }
+}
+
+impl<T: Ord, A: Alloc> Ord for Vector<T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self[..].cmp(&other[..])
+    }
+}
+
+// hashes like the slice so it agrees with `Vec` and `[T]`
+impl<T: Hash, A: Alloc> Hash for Vector<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state)
+    }
+}
+
+impl<T> Default for Vector<T> {
+    fn default() -> Self {
+        Vector::new()
+    }
+}
+
+impl<T, A: Alloc> Clone for Vector<T, A>
+where
+    T: Clone,
+    A: Clone,
+{
+    fn clone(&self) -> Self {
+        let mut vec = Vector { buff: self.buff.alloc_same_cap(), len: 0 };
+        for item in self.iter() {
+            unsafe {
+                ptr::write(vec.ptr().add(vec.len), item.clone());
+            }
+            // counted as it is written so a panicking `clone` drops only these
+            vec.len += 1;
+        }
+        vec
+    }
+
+    // reuses our buffer when it already holds `source.len()` elements
+    fn clone_from(&mut self, source: &Self) {
+        if self.cap() < source.len() {
+            *self = source.clone();
+            return;
+        }
+        self.truncate(source.len());
+        let (init, tail) = source.split_at(self.len);
+        self.deref_mut().clone_from_slice(init);
+        for item in tail {
+            self.push(item.clone());
+        }
+    }
+}
+
+impl<T, A: Alloc> Drop for Vector<T, A> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+impl<T, A: Alloc> AsRef<[T]> for Vector<T, A> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, A: Alloc> Deref for Vector<T, A> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            ::std::slice::from_raw_parts(self.ptr(), self.len)
+        }
+    }
+}
+impl<T, A: Alloc> DerefMut for Vector<T, A> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe {
+            ::std::slice::from_raw_parts_mut(self.ptr(), self.len)
+        }
+    }
+}
+impl<T, A: Alloc> Index<usize> for Vector<T, A> {
+    type Output = T;
+    fn index(&self, idx: usize) -> &T {
+        assert!(idx < self.len, "Vector index {} out of bounds {}", idx, self.len);
+        unsafe { &*self.ptr().add(idx) }
+    }
+}
+
+impl<T, A: Alloc> IndexMut<usize> for Vector<T, A> {
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        assert!(idx < self.len, "Vector index {} out of bounds {}", idx, self.len);
+        unsafe { &mut *self.ptr().add(idx) }
+    }
+}
+
+macro_rules! impl_range_index {
+    ($($range:ty),*) => { $(
+        impl<T, A: Alloc> Index<$range> for Vector<T, A> {
+            type Output = [T];
+            fn index(&self, range: $range) -> &[T] {
+                let (start, end) = self.bounds(range);
+                unsafe { std::slice::from_raw_parts(self.ptr().add(start), end - start) }
+            }
+        }
+
+        impl<T, A: Alloc> IndexMut<$range> for Vector<T, A> {
+            fn index_mut(&mut self, range: $range) -> &mut [T] {
+                let (start, end) = self.bounds(range);
+                unsafe { std::slice::from_raw_parts_mut(self.ptr().add(start), end - start) }
+            }
+        }
+    )* };
+}
+impl_range_index!(
+    Range<usize>, RangeFrom<usize>, RangeFull, RangeInclusive<usize>, RangeTo<usize>,
+    RangeToInclusive<usize>
+);
+
+impl<T, A: Alloc> fmt::Debug for Vector<T, A>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[ ")?;
+        for (idx, x) in self.iter().enumerate() {
+            if (self.len() - 1) == idx {
+                return write!(f, "{:?} ]", x)
+            }
+            write!(f, "{:?}, ", x)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T> From<Vec<T>> for Vector<T> {
+    fn from(mut vec: Vec<T>) -> Vector<T> {
+        let ptr = vec.as_mut_ptr();
+        let cap = vec.capacity();
+        let len = vec.len();
+
+        mem::forget(vec);
+
+        unsafe { Vector::from_raw_parts(ptr, len, cap) }
+    }
+}
+
+impl<T: Clone> From<&[T]> for Vector<T> {
+    fn from(slice: &[T]) -> Vector<T> {
+        let mut vec = Vector::with_capacity(slice.len());
+        for item in slice {
+            vec.push(item.clone());
+        }
+        vec
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vector<T> {
+    fn from(arr: [T; N]) -> Vector<T> {
+        let mut vec = Vector::with_capacity(N);
+        unsafe {
+            ptr::copy_nonoverlapping(arr.as_ptr(), vec.ptr(), N);
+            vec.len = N;
+        }
+        mem::forget(arr);
+        vec
+    }
+}
+
+// `collect` can't tell an `IntoIter` apart on stable, this is the way to
+// get the buffer back without copying into a new one
+impl<T, A: Alloc> From<IntoIter<T, A>> for Vector<T, A> {
+    fn from(iter: IntoIter<T, A>) -> Self {
+        unsafe {
+            let start = iter.iter.start;
+            let len = iter.iter.size_hint().0;
+            let buff = ptr::read(&iter._buff);
+            mem::forget(iter);
+
+            // the front was taken, slide what is left down to the start
+            if !ptr::eq(start, buff.ptr.as_ptr()) {
+                ptr::copy(start, buff.ptr.as_ptr(), len);
+            }
+            Vector { buff, len }
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Vector<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut res = Vector::with_capacity(iter.size_hint().0);
+        for item in iter {
+            res.push(item);
+        }
+        res
+    }
+}
+
+impl<T, A: Alloc> IntoIterator for Vector<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+    fn into_iter(self) -> IntoIter<T, A> {
+        unsafe {
+            let iter = RawIter::new(&self);
+            let buff = ptr::read(&self.buff);
+
+            mem::forget(self);
+
+            IntoIter {
+                _buff: buff,
+                iter,
+            }
+        }
+    }
+}
+
+impl<'a, T, A: Alloc> IntoIterator for &'a Vector<T, A> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Alloc> IntoIterator for &'a mut Vector<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, A: Alloc> Extend<T> for Vector<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Alloc> Extend<&'a T> for Vector<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T> From<Vector<T>> for Vec<T> {
+    fn from(vector: Vector<T>) -> Vec<T> {
+        let ptr = vector.ptr();
+        let cap = vector.cap();
+        let len = vector.len();
+
+        mem::forget(vector);
+
+        // nothing was allocated, a ZST's `!0` capacity is ours not `Vec`'s
+        if cap == 0 {
+            return Vec::new();
+        }
+        if mem::size_of::<T>() == 0 {
+            // reading a ZST is a no-op, this only sets the length
+            return std::iter::repeat_with(|| unsafe { ptr::read(ptr) }).take(len).collect();
+        }
+        unsafe {
+            Vec::from_raw_parts(ptr, len, cap)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, A: Alloc> serde::Serialize for Vector<T, A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Vector<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VectorVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for VectorVisitor<T> {
+            type Value = Vector<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_seq<S: serde::de::SeqAccess<'de>>(self, mut seq: S) -> Result<Vector<T>, S::Error> {
+                // the hint comes from the input so don't trust it with a huge allocation
+                let mut vec = Vector::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                while let Some(item) = seq.next_element()? {
+                    vec.push(item);
+                }
+                Ok(vec)
+            }
+        }
+
+        deserializer.deserialize_seq(VectorVisitor(PhantomData))
+    }
+}
+
+
+struct RawIter<T> {
+    start: *const T,
+    end: *const T,
+}
+impl<T> RawIter<T> {
+    unsafe fn new(slice: &[T]) -> Self {
+        RawIter{
+            start: slice.as_ptr(),
+            end: if mem::size_of::<T>() == 0 {
+                (slice.as_ptr() as usize).wrapping_add(slice.len()) as *const _
+            } else if slice.is_empty() {
+                slice.as_ptr()
+            } else {
+                slice.as_ptr().add(slice.len())
+            }
+        }
+    }
+}
+impl<T> Iterator for RawIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            None
+        } else {
+            unsafe {
+                let res = if mem::size_of::<T>() == 0 {
+                    // only `end` counts down for a ZST so `start` stays aligned
+                    self.end = (self.end as usize - 1) as *const _;
+                    ptr::read(NonNull::dangling().as_ptr())
+                } else {
+                    let res = ptr::read(self.start);
+                    self.start = self.start.add(1);
+                    res
+                };
+                Some(res)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let item_size = mem::size_of::<T>();
+        let len = (self.end as usize).wrapping_sub(self.start as usize)
+            / if item_size == 0 { 1 } else { item_size };
+        (len, Some(len))
+    }
+}
+impl<T> DoubleEndedIterator for RawIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            unsafe {
+                if mem::size_of::<T>() == 0 {
+                    self.end = (self.end as usize - 1) as *const _;
+                    Some(ptr::read(NonNull::dangling().as_ptr()))
+                } else {
+                    self.end = self.end.sub(1);
+                    Some(ptr::read(self.end))
+                }
+            }
+        }
+    }
+}
+
+
+pub struct IntoIter<T, A: Alloc = Global> {
+    _buff: RawVec<T, A>,
+    iter: RawIter<T>,
+}
+unsafe impl<T: Send, A: Alloc + Send> Send for IntoIter<T, A> {}
+unsafe impl<T: Sync, A: Alloc + Sync> Sync for IntoIter<T, A> {}
+impl<T, A: Alloc> IntoIter<T, A> {
+    /// The elements not yet yielded from either end.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.iter.start, self.iter.size_hint().0) }
+    }
+
+    /// The elements not yet yielded from either end, mutably.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe {
+            std::slice::from_raw_parts_mut(self.iter.start as *mut T, self.iter.size_hint().0)
+        }
+    }
+}
+impl<T, A: Alloc> Iterator for IntoIter<T, A> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> { self.iter.next() }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+    fn last(mut self) -> Option<T> {
+        let last = self.iter.next_back()?;
+        unsafe {
+            let start = self.iter.start as *mut T;
+            let len = self.iter.size_hint().0;
+            // mark everything taken first so a panicking drop can't drop twice
+            self.iter.start = self.iter.end;
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(start, len));
+        }
+        Some(last)
+    }
+}
+impl<T, A: Alloc> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> { self.iter.next_back() }
+}
+impl<T, A: Alloc> ExactSizeIterator for IntoIter<T, A> {}
+impl<T, A: Alloc> FusedIterator for IntoIter<T, A> {}
+impl<T, A: Alloc> Drop for IntoIter<T, A> {
+    fn drop(&mut self) { for _ in &mut *self {} }
+}
+
+
+pub struct Drain<'a, T, A: Alloc = Global> {
+    _vec: PhantomData<&'a mut Vector<T, A>>,
+    vec: NonNull<Vector<T, A>>,
+    iter: RawIter<T>,
+    tail_start: usize,
+    tail_len: usize,
+}
+// behaves like the `&mut Vector` it borrows
+unsafe impl<'a, T: Send, A: Alloc + Send> Send for Drain<'a, T, A> {}
+unsafe impl<'a, T: Sync, A: Alloc + Sync> Sync for Drain<'a, T, A> {}
+impl<'a, T, A: Alloc> Drain<'a, T, A> {
+    /// Stops draining, the elements not yet yielded stay in the vector in
+    /// their place in front of the tail.
+    pub fn keep_rest(self) {
+        let mut this = mem::ManuallyDrop::new(self);
+        unsafe {
+            let vec = this.vec.as_mut();
+            let left = this.iter.size_hint().0;
+            let dst = vec.ptr().add(vec.len);
+            // the unyielded elements move down over the yielded ones
+            ptr::copy(this.iter.start, dst, left);
+            vec.len += left;
+            this.iter.start = this.iter.end;
+            this.move_tail();
+        }
+    }
+
+    // shift the tail down to `vec.len` and count it back in
+    unsafe fn move_tail(&mut self) {
+        let vec = self.vec.as_mut();
+        let start = vec.len;
+        if self.tail_start != start {
+            ptr::copy(
+                vec.ptr().add(self.tail_start),
+                vec.ptr().add(start),
+                self.tail_len,
+            );
+        }
+        vec.len = start + self.tail_len;
+    }
+}
+impl<'a, T, A: Alloc> Iterator for Drain<'a, T, A> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> { self.iter.next() }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+impl<'a, T, A: Alloc> DoubleEndedIterator for Drain<'a, T, A> {
+    fn next_back(&mut self) -> Option<T> { self.iter.next_back() }
+}
+impl<'a, T, A: Alloc> ExactSizeIterator for Drain<'a, T, A> {}
+impl<'a, T, A: Alloc> FusedIterator for Drain<'a, T, A> {}
+// The vector's `len` stops before the drained range until this drop puts
+// the tail back, so a forgotten `Drain` only leaks the range and the tail.
+impl<'a, T, A: Alloc> Drop for Drain<'a, T, A> {
+    fn drop(&mut self) {
+        // the tail is put back even if dropping a drained elem panics
+        struct TailGuard<'r, 'a, T, A: Alloc>(&'r mut Drain<'a, T, A>);
+        impl<'r, 'a, T, A: Alloc> Drop for TailGuard<'r, 'a, T, A> {
+            fn drop(&mut self) {
+                unsafe { self.0.move_tail() }
+            }
+        }
+
+        let guard = TailGuard(self);
+        for _ in &mut *guard.0 {}
+    }
+}
+
+pub struct ExtractIf<'a, T, F, A: Alloc = Global>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    vec: &'a mut Vector<T, A>,
+    // next elem to check
+    idx: usize,
+    deleted: usize,
+    old_len: usize,
+    pred: F,
+}
+impl<'a, T, F, A: Alloc> Iterator for ExtractIf<'a, T, F, A>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        while self.idx < self.old_len {
+            unsafe {
+                let cur = self.vec.ptr().add(self.idx);
+                // `idx` moves past `cur` only once `pred` returns, if it
+                // panics `cur` is kept along with the rest of the tail
+                let take = (self.pred)(&mut *cur);
+                self.idx += 1;
+                if take {
+                    self.deleted += 1;
+                    return Some(ptr::read(cur));
+                }
+                if self.deleted > 0 {
+                    ptr::copy_nonoverlapping(cur, cur.sub(self.deleted), 1);
+                }
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.old_len - self.idx))
+    }
+}
+impl<'a, T, F, A: Alloc> Drop for ExtractIf<'a, T, F, A>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    fn drop(&mut self) {
+        unsafe {
+            if self.deleted > 0 {
+                ptr::copy(
+                    self.vec.ptr().add(self.idx),
+                    self.vec.ptr().add(self.idx - self.deleted),
+                    self.old_len - self.idx,
+                );
+            }
+        }
+        self.vec.len = self.old_len - self.deleted;
+    }
+}
+
+#[macro_export]
+macro_rules! vector {
+    ($elem:expr; $n:expr) => {
+        $crate::Vector::from_elem($elem, $n)
+    };
+    ($($item:expr),*) => {
+        {
+            #[allow(unused_mut)]
+            let mut res = $crate::Vector::new();
+            $( res.push($item); )*
+            res
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_of<T>(_: &T) -> &str {
+        std::any::type_name::<T>()
+    }
+
+    #[test]
+    fn test_vec_insert() {
+        let mut vec = Vector::new();
+        vec.push(1);
+        vec.push(2);
+        vec.push(3);
+
+        let val = vec.pop();
+        println!("whole enchilada {:?}", vec);
+        assert_eq!(val, Some(3));
+    }
+
+    #[test]
+    fn test_vec_into_iter() {
+        let mut vec = Vector::new();
+        vec.push(1);
+        vec.push(2);
+        vec.push(3);
+        vec.push(4);
+        vec.push(5);
+
+        println!("{:?}", vec);
+        for (idx, val) in vec.into_iter().enumerate() {
+            println!("found {:?}", val);
+            assert_eq!(val, idx + 1);
+        }
+    }
+
+    #[test]
+    fn test_vec_insert_iter_at() {
+        let mut vec = vector![1, 2, 3, 4, 5];
+        vec.insert_iter_at(2, vec![10, 20, 30]);
+        assert_eq!(vec, vector![1, 2, 10, 20, 30, 3, 4, 5]);
+
+        vec.insert_iter_at(vec.len(), vec![6]);
+        assert_eq!(vec, vector![1, 2, 10, 20, 30, 3, 4, 5, 6]);
+        vec.insert_iter_at(0, Vec::new());
+        assert_eq!(vec.len(), 9);
+
+        // one reservation for the whole iterator
+        let mut empty = Vector::new();
+        empty.insert_iter_at(0, 0..100);
+        assert_eq!(empty.capacity(), 100);
+    }
+
+    #[test]
+    fn test_vec_clear_and_shrink() {
+        let mut vec = vector!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert!(vec.cap() >= 3);
+
+        vec.clear_and_shrink();
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.cap(), 0);
+
+        vec.push("d".to_string());
+        assert_eq!(vec, vector!["d".to_string()]);
+    }
+
+    #[test]
+    fn test_vec_checksum_u16() {
+        // example from RFC 1071 section 3, the sum is 0xddf2
+        let bytes: Vector<u8> = Vector::from(vec![0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7]);
+        assert_eq!(bytes.checksum_u16(), !0xddf2);
+
+        // appending the checksum makes the whole buffer verify to 0
+        let mut check = Vec::from(bytes.deref());
+        check.extend_from_slice(&bytes.checksum_u16().to_be_bytes());
+        assert_eq!(Vector::from(check).checksum_u16(), 0);
+
+        let odd: Vector<u8> = vector![0x01];
+        assert_eq!(odd.checksum_u16(), !0x0100);
+        let empty: Vector<u8> = Vector::new();
+        assert_eq!(empty.checksum_u16(), 0xffff);
+    }
+
+    #[test]
+    fn test_vec_swap_remove_front() {
+        let mut vec = vector![1, 2, 3, 4];
+        assert_eq!(vec.swap_remove_front(), Some(1));
+        assert_eq!(vec, vector![4, 2, 3]);
+        assert_eq!(vec.swap_remove_front(), Some(4));
+        assert_eq!(vec, vector![3, 2]);
+
+        let mut single = vector!["a".to_string()];
+        assert_eq!(single.swap_remove_front(), Some("a".to_string()));
+        assert_eq!(single.len(), 0);
+        assert_eq!(single.swap_remove_front(), None);
+    }
+
+    #[test]
+    fn test_vec_fill_range() {
+        let mut vec = vector![1, 2, 3, 4, 5, 6];
+        vec.fill_range(2..4, 0);
+        assert_eq!(vec, vector![1, 2, 0, 0, 5, 6]);
+        vec.fill_range(4.., 9);
+        assert_eq!(vec, vector![1, 2, 0, 0, 9, 9]);
+        vec.fill_range(1..1, 7);
+        assert_eq!(vec, vector![1, 2, 0, 0, 9, 9]);
+
+        let mut strings = vector!["a".to_string(), "b".to_string(), "c".to_string()];
+        strings.fill_range(..=1, "z".to_string());
+        assert_eq!(strings, vector!["z".to_string(), "z".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_vec_fill_range_out_of_bounds() {
+        let mut vec = vector![1, 2, 3];
+        vec.fill_range(1..4, 0);
+    }
+
+    #[test]
+    fn test_vec_sort_dedup() {
+        let mut vec = vector![3, 1, 2, 3, 1];
+        vec.sort_dedup();
+        assert_eq!(vec, vector![1, 2, 3]);
+
+        let mut words = vector!["b".to_string(), "a".to_string(), "b".to_string()];
+        words.sort_dedup();
+        assert_eq!(words, vector!["a".to_string(), "b".to_string()]);
+
+        let mut empty: Vector<u8> = Vector::new();
+        empty.sort_dedup();
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn test_vec_extend_vector() {
+        let big = vector![1, 2, 3, 4, 5];
+        let ptr = big.as_ptr();
+
+        let mut vec = Vector::new();
+        vec.extend_vector(big.into_iter());
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec, vector![1, 2, 3, 4, 5]);
+
+        // not empty so the items are moved over
+        vec.extend_vector(vector![6, 7].into_iter());
+        assert_eq!(vec, vector![1, 2, 3, 4, 5, 6, 7]);
+
+        let mut iter = vector![1, 2, 3].into_iter();
+        iter.next();
+        let mut rest = Vector::new();
+        rest.extend_vector(iter);
+        assert_eq!(rest, vector![2, 3]);
+    }
+
+    #[test]
+    fn test_vec_enumerate_rev() {
+        let vec = vector!['a', 'b', 'c'];
+        let pairs = vec.enumerate_rev().map(|(i, c)| (i, *c)).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(2, 'c'), (1, 'b'), (0, 'a')]);
+
+        let empty: Vector<char> = Vector::new();
+        assert_eq!(empty.enumerate_rev().count(), 0);
+    }
+
+    #[test]
+    fn test_vec_into_chunks() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(u8);
+
+        let chunks = vector![NoClone(1), NoClone(2), NoClone(3), NoClone(4), NoClone(5)].into_chunks(2);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], vector![NoClone(1), NoClone(2)]);
+        assert_eq!(chunks[2], vector![NoClone(5)]);
+
+        // no short final chunk when evenly divisible
+        let chunks = vector![1, 2, 3, 4, 5, 6].into_chunks(3);
+        assert_eq!(chunks, vector![vector![1, 2, 3], vector![4, 5, 6]]);
+
+        let chunks = vector![1, 2, 3].into_chunks(10);
+        assert_eq!(chunks, vector![vector![1, 2, 3]]);
+
+        let empty: Vector<u8> = Vector::new();
+        assert_eq!(empty.into_chunks(4).len(), 0);
+    }
+
+    #[test]
+    fn test_vec_cumulative() {
+        let vec = vector![1, 2, 3, 4];
+        let sums = vec.cumulative(|acc, x| acc + x);
+        assert_eq!(sums, vector![1, 3, 6, 10]);
+        assert_eq!(sums.cap(), 4);
+
+        let maxes = vector![2.0, 1.0, 3.5, 0.5].cumulative(|acc: &f64, x| acc.max(*x));
+        assert_eq!(maxes, vector![2.0, 2.0, 3.5, 3.5]);
+
+        let empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.cumulative(|acc, x| acc + x).len(), 0);
+    }
+
+    #[test]
+    fn test_vec_retain_indexed() {
+        let mut vec = vector!["a", "b", "c", "d", "e", "f", "g"];
+        // every third elem by original position
+        vec.retain_indexed(|idx, _| idx % 3 != 2);
+        assert_eq!(vec, vector!["a", "b", "d", "e", "g"]);
+
+        let mut seen = Vec::new();
+        let mut vec = vector![10, 20, 30, 40];
+        vec.retain_indexed(|idx, x| {
+            seen.push(idx);
+            *x != 20
+        });
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+        assert_eq!(vec, vector![10, 30, 40]);
+    }
+
+    #[test]
+    fn test_vec_retain_indexed_panic_safe() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut vec = Vector::new();
+        for _ in 0..6 {
+            vec.push(Rc::clone(&counter));
+        }
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            vec.retain_indexed(|idx, _| {
+                if idx == 4 {
+                    panic!("predicate blew up");
+                }
+                idx % 2 == 0
+            });
+        }));
+        assert!(res.is_err());
+        // 1 and 3 were dropped, 4 and 5 were never checked and are kept
+        assert_eq!(vec.len(), 4);
+        assert_eq!(Rc::strong_count(&counter), 5);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[ignore = "known regression: collect no longer reuses an IntoIter's buffer on stable"]
+    fn test_vec_collect_round_trip() {
+        let vec = vector![1, 2, 3, 4];
+        let ptr = vec.as_ptr();
+        let back: Vector<_> = vec.into_iter().collect();
+        assert_eq!(back.as_ptr(), ptr);
+        assert_eq!(back, vector![1, 2, 3, 4]);
+
+        let mut iter = back.into_iter();
+        iter.next();
+        let rest: Vector<_> = iter.collect();
+        assert_eq!(rest, vector![2, 3, 4]);
+
+        let doubled: Vector<_> = rest.iter().map(|x| x * 2).collect();
+        assert_eq!(doubled, vector![4, 6, 8]);
+    }
+
+    #[test]
+    fn test_vec_from_into_iter() {
+        let vec = vector![1, 2, 3, 4];
+        let ptr = vec.as_ptr();
+        let back = Vector::from(vec.into_iter());
+        assert_eq!(back.as_ptr(), ptr);
+        assert_eq!(back, vector![1, 2, 3, 4]);
+
+        let mut iter = back.into_iter();
+        iter.next();
+        iter.next_back();
+        let middle = Vector::from(iter);
+        assert_eq!(middle.as_ptr(), ptr);
+        assert_eq!(middle, vector![2, 3]);
+    }
+
+    #[test]
+    fn test_vec_run_length_encode() {
+        let vec = vector!['a', 'a', 'b', 'c', 'c', 'c'];
+        assert_eq!(vec.run_length_encode(), vector![('a', 2), ('b', 1), ('c', 3)]);
+        assert_eq!(vec.len(), 6);
+
+        let empty: Vector<char> = Vector::new();
+        assert_eq!(empty.run_length_encode().len(), 0);
+
+        let long = vector![1; 1000];
+        assert_eq!(long.run_length_encode(), vector![(1, 1000)]);
+
+        let runs = vec.run_length_encode();
+        assert_eq!(runs.capacity(), runs.len());
+    }
+
+    #[test]
+    fn test_vec_split_into() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(u8);
+
+        let vec = vector![NoClone(1), NoClone(2), NoClone(3), NoClone(4), NoClone(5)];
+        let (head, tail) = vec.split_into(2);
+        assert_eq!(head, vector![NoClone(1), NoClone(2)]);
+        assert_eq!(tail, vector![NoClone(3), NoClone(4), NoClone(5)]);
+
+        let (head, tail) = vector![1, 2].split_into(2);
+        assert_eq!(head, vector![1, 2]);
+        assert_eq!(tail.len(), 0);
+        let (head, tail) = vector![1, 2].split_into(0);
+        assert_eq!(head.len(), 0);
+        assert_eq!(tail, vector![1, 2]);
+    }
+
+    #[test]
+    fn test_vec_into_iter_last() {
+        use std::rc::Rc;
+
+        let vec = vector!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(vec.into_iter().last(), Some("c".to_string()));
+
+        let counter = Rc::new(());
+        let mut vec = Vector::new();
+        for _ in 0..5 {
+            vec.push(Rc::clone(&counter));
+        }
+        let mut iter = vec.into_iter();
+        iter.next();
+        let last = iter.last();
+        assert!(last.is_some());
+        assert_eq!(Rc::strong_count(&counter), 2);
+        drop(last);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        let empty: Vector<String> = Vector::new();
+        assert_eq!(empty.into_iter().last(), None);
+    }
+
+    #[test]
+    fn test_vec_extend_from_within() {
+        let mut vec = vector!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()];
+        assert_eq!(vec.cap(), 4);
+        vec.extend_from_within(..3);
+        assert!(vec.cap() > 4);
+        assert_eq!(vec.len(), 7);
+        assert_eq!(&vec[4..], &["a".to_string(), "b".to_string(), "c".to_string()][..]);
+
+        let mut vec = vector![1, 2, 3];
+        vec.extend_from_within(1..1);
+        assert_eq!(vec, vector![1, 2, 3]);
+        vec.extend_from_within(2..=2);
+        assert_eq!(vec, vector![1, 2, 3, 3]);
+    }
+
+    #[test]
+    fn test_vec_extend_from_slice() {
+        let mut vec: Vector<u8> = Vector::new();
+        vec.extend_from_slice(b"ab");
+        vec.extend_from_slice(&[]);
+        vec.extend_from_slice(b"cde");
+        assert_eq!(vec, *b"abcde");
+
+        // the whole buffer is the source of a copy that has to grow
+        let mut words = Vector::from(["x".to_string(), "y".to_string()]);
+        assert_eq!(words.capacity(), 2);
+        words.extend_from_within(..);
+        words.extend_from_within(..);
+        assert_eq!(words, ["x", "y", "x", "y", "x", "y", "x", "y"]);
+    }
+
+    #[test]
+    fn test_vec_sort_by_cached_key() {
+        let mut calls = 0;
+        let mut vec = vector!["ccc", "a", "eeeee", "bb", "dddd"];
+        vec.sort_by_cached_key(|s| {
+            calls += 1;
+            s.len()
+        });
+        assert_eq!(vec, vector!["a", "bb", "ccc", "dddd", "eeeee"]);
+        assert_eq!(calls, vec.len());
+    }
+
+    #[test]
+    fn test_vec_remove_item() {
+        let mut vec = vector![1, 2, 3, 2, 4];
+        assert_eq!(vec.remove_item(&2), Some(2));
+        assert_eq!(vec, vector![1, 3, 2, 4]);
+        assert_eq!(vec.remove_item(&9), None);
+        assert_eq!(vec.len(), 4);
+
+        assert_eq!(vec.swap_remove_item(&1), Some(1));
+        assert_eq!(vec, vector![4, 3, 2]);
+        assert_eq!(vec.swap_remove_item(&2), Some(2));
+        assert_eq!(vec, vector![4, 3]);
+        assert_eq!(vec.swap_remove_item(&9), None);
+    }
+
+    #[test]
+    fn test_vec_unique() {
+        let vec = vector![3, 1, 3, 2, 1];
+        assert_eq!(vec.unique(), vector![3, 1, 2]);
+        assert_eq!(vec.len(), 5);
+
+        let empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.unique().len(), 0);
+    }
+
+    #[test]
+    fn test_vec_with_separator() {
+        let joined = vector![1, 2, 3].with_separator(0);
+        assert_eq!(joined, vector![1, 0, 2, 0, 3]);
+        assert_eq!(joined.capacity(), 5);
+        assert_eq!(vector![1].with_separator(0), vector![1]);
+
+        let empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.with_separator(0).len(), 0);
+    }
+
+    #[test]
+    fn test_vec_try_reserve_exact() {
+        let mut vec: Vector<u32> = vector![1];
+        assert_eq!(vec.try_reserve_exact(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        // fits in usize but not in isize::MAX bytes
+        assert_eq!(vec.try_reserve_exact(usize::MAX / 2), Err(TryReserveError::CapacityOverflow));
+
+        let mut bytes: Vector<u8> = Vector::new();
+        assert_eq!(bytes.try_reserve_exact(isize::MAX as usize - 64), Err(TryReserveError::AllocError));
+        assert_eq!(bytes.cap(), 0);
+
+        assert_eq!(vec.try_reserve_exact(9), Ok(()));
+        assert_eq!(vec.cap(), 10);
+        assert_eq!(vec.try_reserve_exact(3), Ok(()));
+        assert_eq!(vec.cap(), 10);
+        assert_eq!(vec, vector![1]);
+    }
+
+    #[test]
+    fn test_vec_remove_range() {
+        let mut vec = vector![1, 2, 3, 4, 5, 6];
+        vec.remove_range(1..4);
+        assert_eq!(vec, vector![1, 5, 6]);
+        vec.remove_range(2..2);
+        assert_eq!(vec, vector![1, 5, 6]);
+        vec.remove_range(..);
+        assert_eq!(vec.len(), 0);
+
+        let mut strings = vector!["a".to_string(), "b".to_string(), "c".to_string()];
+        strings.remove_range(..=1);
+        assert_eq!(strings, vector!["c".to_string()]);
+    }
+
+    #[test]
+    fn test_vec_bytes_round_trip() {
+        let vec = vector![1_u32, 0xdead_beef, u32::MAX, 0];
+        let bytes = vec.as_bytes();
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(&bytes[4..8], &0xdead_beef_u32.to_ne_bytes());
+
+        // an unaligned copy of the bytes still reads back the same
+        let mut shifted = vec![0_u8];
+        shifted.extend_from_slice(bytes);
+        let back = unsafe { Vector::<u32>::from_bytes_copy(&shifted[1..]) };
+        assert_eq!(back, vec);
+        assert_eq!(back.capacity(), 4);
+    }
+
+    #[test]
+    fn test_vec_chunks_mut_vec() {
+        let mut vec = (0..10).collect::<Vector<i32>>();
+        let chunks = vec.chunks_mut_vec(3);
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![4, 3, 3]);
+        for chunk in chunks {
+            for x in chunk.iter_mut() {
+                *x *= 10;
+            }
+        }
+        // every element was covered exactly once
+        assert_eq!(vec, (0..10).map(|x| x * 10).collect::<Vector<i32>>());
+
+        let mut short = vector![1, 2];
+        assert_eq!(short.chunks_mut_vec(5).len(), 2);
+        assert!(Vector::<i32>::new().chunks_mut_vec(4).is_empty());
+    }
+
+    #[test]
+    fn test_vec_group_by_key() {
+        #[derive(Debug, PartialEq)]
+        struct Log(u8, &'static str);
+
+        let logs = vector![Log(1, "a"), Log(1, "b"), Log(3, "c"), Log(1, "d"), Log(1, "e"), Log(1, "f")];
+        let groups = logs.group_by_key(|log| log.0);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0], vector![Log(1, "a"), Log(1, "b")]);
+        assert_eq!(groups[1], vector![Log(3, "c")]);
+        assert_eq!(groups[2], vector![Log(1, "d"), Log(1, "e"), Log(1, "f")]);
+
+        assert!(Vector::<Log>::new().group_by_key(|log| log.0).is_empty());
+    }
+
+    #[test]
+    fn test_vec_clone_owns_its_buffer() {
+        let orig = vector!["one".to_string(), "two".to_string(), "three".to_string()];
+        let clone = orig.clone();
+        assert_ne!(orig.as_ptr(), clone.as_ptr());
+        assert_eq!(clone.cap(), orig.cap());
+        drop(orig);
+        assert_eq!(clone, vector!["one".to_string(), "two".to_string(), "three".to_string()]);
+
+        let empty = Vector::<String>::new();
+        assert_eq!(empty.clone().len(), 0);
+    }
+
+    #[test]
+    fn test_vec_with_capacity() {
+        let mut vec = Vector::with_capacity(10);
+        assert_eq!(vec.cap(), 10);
+        assert_eq!(vec.len(), 0);
+        let ptr = vec.as_ptr();
+        for i in 0..10 {
+            vec.push(i);
+        }
+        assert_eq!(vec.cap(), 10);
+        assert_eq!(vec.as_ptr(), ptr);
+
+        assert_eq!(Vector::<u8>::with_capacity(0).cap(), 0);
+        assert_eq!(Vector::<()>::with_capacity(5).cap(), !0);
+    }
+
+    #[test]
+    fn test_vec_drain_range() {
+        let mut vec = (0..8).map(|i| i.to_string()).collect::<Vector<_>>();
+        let drained = vec.drain(2..5).collect::<Vec<_>>();
+        assert_eq!(drained, ["2", "3", "4"]);
+        assert_eq!(vec.iter().map(|s| s.as_str()).collect::<Vec<_>>(), ["0", "1", "5", "6", "7"]);
+
+        // dropped part way through the rest of the range is still removed
+        let mut drain = vec.drain(1..=3);
+        assert_eq!(drain.next_back(), Some("6".to_string()));
+        drop(drain);
+        assert_eq!(vec.iter().map(|s| s.as_str()).collect::<Vec<_>>(), ["0", "7"]);
+
+        vec.drain(2..);
+        assert_eq!(vec.drain(..).count(), 2);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_vec_drain_forget_and_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut vec = (0..6).map(|i| (i, Rc::clone(&counter))).collect::<Vector<_>>();
+        let mut drain = vec.drain(1..4);
+        drain.next();
+        mem::forget(drain);
+        // the vector only keeps what is before the range, the rest leaks
+        assert_eq!(vec.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0]);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&counter), 5);
+
+        struct PanicOnDrop(bool);
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                if self.0 {
+                    panic!("drop panic");
+                }
+            }
+        }
+        let mut vec = vector![PanicOnDrop(false), PanicOnDrop(true), PanicOnDrop(false), PanicOnDrop(false)];
+        let res = catch_unwind(AssertUnwindSafe(|| drop(vec.drain(1..3))));
+        assert!(res.is_err());
+        assert_eq!(vec.len(), 2);
+        assert!(vec.iter().all(|p| !p.0));
+    }
+
+    #[test]
+    fn test_vec_exact_size_iters() {
+        let mut iter = (0..5).collect::<Vector<i32>>().into_iter();
+        assert_eq!(iter.len(), 5);
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.len(), 3);
+        iter.by_ref().for_each(drop);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+
+        let mut vec = (0..5).collect::<Vector<i32>>();
+        let mut drain = vec.drain(1..4);
+        assert_eq!(drain.len(), 3);
+        drain.next_back();
+        assert_eq!(drain.len(), 2);
+        drain.next();
+        assert_eq!(drain.len(), 1);
+        drop(drain);
+        assert_eq!(vec, vector![0, 4]);
+
+        let mut units = vector![(), (), ()].into_iter();
+        units.next_back();
+        assert_eq!(units.len(), 2);
+    }
+
+    #[test]
+    fn test_vec_drain_keep_rest() {
+        let mut vec = (0..6).map(|i| i.to_string()).collect::<Vector<_>>();
+        vec.drain(1..4).keep_rest();
+        assert_eq!(vec, (0..6).map(|i| i.to_string()).collect::<Vector<_>>());
+
+        let mut drain = vec.drain(1..4);
+        assert_eq!(drain.next(), Some("1".to_string()));
+        drain.keep_rest();
+        assert_eq!(vec, vector!["0".to_string(), "2".to_string(), "3".to_string(), "4".to_string(), "5".to_string()]);
+
+        let mut drain = vec.drain(1..3);
+        assert_eq!(drain.next_back(), Some("3".to_string()));
+        assert_eq!(drain.next(), Some("2".to_string()));
+        drain.keep_rest();
+        assert_eq!(vec, vector!["0".to_string(), "4".to_string(), "5".to_string()]);
+
+        let mut drain = vec.drain(..);
+        assert_eq!(drain.next(), Some("0".to_string()));
+        drain.keep_rest();
+        assert_eq!(vec, vector!["4".to_string(), "5".to_string()]);
+    }
+
+    #[test]
+    fn test_vec_capacity() {
+        assert_eq!(Vector::<i32>::with_capacity(10).capacity(), 10);
+
+        let mut vec = Vector::new();
+        let mut cap = vec.capacity();
+        for i in 0..100 {
+            vec.push(i);
+            assert!(vec.capacity() >= cap);
+            assert!(vec.capacity() >= vec.len());
+            cap = vec.capacity();
+        }
+        assert_eq!(vec.len(), 100);
+    }
+
+    #[test]
+    fn test_vec_reserve() {
+        let mut vec = Vector::with_capacity(8);
+        vec.push(1);
+        let ptr = vec.as_ptr();
+        vec.reserve(7);
+        vec.reserve_exact(3);
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(vec.as_ptr(), ptr);
+
+        vec.reserve(9);
+        assert_eq!(vec.capacity(), 16);
+        vec.reserve_exact(1_000_000);
+        assert_eq!(vec.capacity(), 1_000_001);
+        vec.extend_from_within(..);
+        assert_eq!(&vec[..], &[1, 1]);
+
+        let mut units = Vector::<()>::with_capacity(0);
+        units.reserve(usize::MAX);
+        assert_eq!(units.capacity(), !0);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_vec_reserve_overflow() {
+        let mut vec = vector![1_u8];
+        vec.reserve(usize::MAX);
+    }
+
+    #[test]
+    fn test_vec_try_reserve() {
+        let mut vec = vector![1_u64, 2];
+        assert_eq!(vec.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        // fits in usize elements but not in isize::MAX bytes
+        assert_eq!(vec.try_reserve(usize::MAX / 8), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(&vec[..], &[1, 2]);
+
+        assert_eq!(vec.try_reserve(10), Ok(()));
+        assert!(vec.capacity() >= 12);
+    }
+
+    #[test]
+    fn test_vec_zst() {
+        let mut units = Vector::new();
+        for _ in 0..1_000_000 {
+            units.push(());
+        }
+        assert_eq!(units.len(), 1_000_000);
+        assert_eq!(units.capacity(), !0);
+        units.insert(10, ());
+        units.remove(0);
+        assert_eq!(units.len(), 1_000_000);
+        while units.pop().is_some() {}
+        assert_eq!(units.len(), 0);
+
+        let mut markers = vector![PhantomData::<String>, PhantomData, PhantomData];
+        assert_eq!(markers[..].len(), 3);
+        markers.drain(1..2).keep_rest();
+        let mut iter = markers.into_iter();
+        assert_eq!(iter.next(), Some(PhantomData));
+        assert_eq!(iter.next_back(), Some(PhantomData));
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!(iter.last(), Some(PhantomData));
+    }
+
+    #[test]
+    fn test_vec_truncate() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut vec = Vector::new();
+        for i in 0..6 {
+            vec.push((i.to_string(), Rc::clone(&counter)));
+        }
+        vec.truncate(10);
+        assert_eq!(vec.len(), 6);
+
+        vec.truncate(2);
+        assert_eq!(vec.len(), 2);
+        assert_eq!(Rc::strong_count(&counter), 3);
+        assert_eq!(vec[1].0, "1");
+        vec.truncate(0);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_vec_clear() {
+        let mut vec = (0..20).map(|i| i.to_string()).collect::<Vector<_>>();
+        let cap = vec.capacity();
+        vec.clear();
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), cap);
+        vec.push("again".to_string());
+        assert_eq!(vec, vector!["again".to_string()]);
+    }
+
+    #[test]
+    fn test_vec_swap_remove() {
+        let mut vec = vector!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()];
+        assert_eq!(vec.swap_remove(3), "d");
+        assert_eq!(vec, vector!["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert_eq!(vec.swap_remove(0), "a");
+        assert_eq!(vec, vector!["c".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "swap_remove index 2 out of bounds 2")]
+    fn test_vec_swap_remove_out_of_bounds() {
+        let mut vec = vector![1, 2];
+        vec.swap_remove(2);
+    }
+
+    #[test]
+    fn test_vec_extend() {
+        let mut vec = vector![0];
+        vec.extend(1..5);
+        assert_eq!(vec.capacity(), 5);
+        vec.extend(vector![5, 6]);
+        vec.extend(&[7, 8]);
+        assert_eq!(vec, (0..9).collect::<Vector<_>>());
+    }
+
+    #[test]
+    fn test_vec_collect() {
+        let collected = (0..20).filter(|x| x % 3 == 0).map(|x| x * 2).collect::<Vector<i32>>();
+        let mut manual = Vector::new();
+        for x in &[0, 6, 12, 18, 24, 30, 36] {
+            manual.push(*x);
+        }
+        assert_eq!(collected, manual);
+
+        // the exact size hint allocates once
+        assert_eq!((0..7).collect::<Vector<i32>>().capacity(), 7);
+        assert_eq!(std::iter::empty::<i32>().collect::<Vector<_>>().capacity(), 0);
+    }
+
+    #[test]
+    fn test_vec_for_loops() {
+        struct NoDebug(i32);
+
+        let mut vec = vector![NoDebug(1), NoDebug(2), NoDebug(3)];
+        for x in &mut vec {
+            x.0 *= 2;
+        }
+        let mut sum = 0;
+        for x in &vec {
+            sum += x.0;
+        }
+        assert_eq!(sum, 12);
+        let mut owned = Vec::new();
+        for x in vec {
+            owned.push(x.0);
+        }
+        assert_eq!(owned, vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn test_vec_many_pushes() {
+        // grow and the iterators used to print on every call
+        let mut vec = Vector::new();
+        for i in 0..100_000 {
+            vec.push(i);
+        }
+        assert_eq!(vec.into_iter().rev().take(3).collect::<Vec<_>>(), vec![99_999, 99_998, 99_997]);
+    }
+
+    #[test]
+    fn test_vec_retain() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut vec = (0..8).map(|i| (i.to_string(), Rc::clone(&counter))).collect::<Vector<_>>();
+        vec.retain(|(s, _)| s.parse::<i32>().unwrap() % 3 != 0);
+        assert_eq!(vec.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>(), ["1", "2", "4", "5", "7"]);
+        assert_eq!(Rc::strong_count(&counter), 6);
+
+        vec.retain_mut(|(s, _)| {
+            s.push('!');
+            s.len() == 2
+        });
+        assert_eq!(vec.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>(), ["1!", "2!", "4!", "5!", "7!"]);
+        vec.retain(|_| false);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_vec_dedup() {
+        let strings = |xs: &[&str]| xs.iter().map(|x| x.to_string()).collect::<Vector<_>>();
+
+        let mut vec = strings(&["a", "a", "a", "b", "c", "c", "b", "d", "d"]);
+        vec.dedup();
+        assert_eq!(vec, strings(&["a", "b", "c", "b", "d"]));
+
+        let mut vec = strings(&["a", "A", "b", "B", "b", "c"]);
+        vec.dedup_by_key(|s| s.to_lowercase());
+        assert_eq!(vec, strings(&["a", "b", "c"]));
+
+        // `b` is the kept element each candidate is compared against
+        let mut readings = vector![1, 2, 3, 10, 11, 30];
+        readings.dedup_by(|a, b| *a - *b < 5);
+        assert_eq!(readings, vector![1, 10, 30]);
+
+        let mut empty = Vector::<String>::new();
+        empty.dedup();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_vec_split_off() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut vec = (0..5).map(|i| (i, Rc::clone(&counter))).collect::<Vector<_>>();
+        let tail = vec.split_off(3);
+        assert_eq!(vec.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(tail.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(tail.capacity(), 2);
+
+        assert!(vec.split_off(3).is_empty());
+        let all = vec.split_off(0);
+        assert_eq!(vec.len(), 0);
+        assert_eq!(all.len(), 3);
+
+        assert_eq!(Rc::strong_count(&counter), 6);
+        drop((vec, tail, all));
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds 2")]
+    fn test_vec_split_off_out_of_bounds() {
+        vector![1, 2].split_off(3);
+    }
 
-        unsafe {
-            Vec::from_raw_parts(ptr, len, cap)
-        }
+    #[test]
+    fn test_vec_append() {
+        let mut dst = vector!["a".to_string(), "b".to_string()];
+        let mut src = vector!["c".to_string(), "d".to_string(), "e".to_string()];
+        let src_cap = src.capacity();
+        dst.append(&mut src);
+        assert_eq!(dst, vector!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string(), "e".to_string()]);
+        assert!(src.is_empty());
+        assert_eq!(src.capacity(), src_cap);
+
+        src.push("f".to_string());
+        dst.append(&mut src);
+        dst.append(&mut Vector::new());
+        assert_eq!(dst.len(), 6);
+        assert_eq!(dst[5], "f");
     }
-}
 
+    #[test]
+    fn test_vec_resize() {
+        let mut vec = vector!["a".to_string()];
+        vec.resize(3, "b".to_string());
+        assert_eq!(vec, vector!["a".to_string(), "b".to_string(), "b".to_string()]);
+        assert_eq!(vec.capacity(), 3);
+        vec.resize(3, "c".to_string());
+        assert_eq!(vec.len(), 3);
+        vec.resize(1, "c".to_string());
+        assert_eq!(vec, vector!["a".to_string()]);
 
-struct RawIter<T> {
-    start: *const T,
-    end: *const T,
-}
-impl<T> RawIter<T> {
-    unsafe fn new(slice: &[T]) -> Self {
-        println!("slice as ptr {:?}", slice.as_ptr());
-        RawIter{
-            start: slice.as_ptr(),
-            end: if mem::size_of::<T>() == 0 {
-                println!("size of is ZERO");
-                ((slice.as_ptr() as usize) + slice.len()) as *const _
-            } else if slice.len() == 0 {
-                println!("len of is ZERO");
-                slice.as_ptr()
-            } else {
-                println!("ptr: {:?} len: {}", slice.as_ptr().offset(slice.len() as isize), slice.len());
-                slice.as_ptr().offset(slice.len() as isize)
+        let mut next = 0;
+        let mut nums = Vector::new();
+        nums.resize_with(4, || {
+            next += 1;
+            next
+        });
+        assert_eq!(nums, vector![1, 2, 3, 4]);
+        nums.resize_with(4, || unreachable!());
+        nums.resize_with(2, || unreachable!());
+        assert_eq!(nums, vector![1, 2]);
+    }
+
+    #[test]
+    fn test_vec_shrink() {
+        let mut vec = (0..1000).collect::<Vector<i32>>();
+        vec.truncate(3);
+        vec.shrink_to(100);
+        assert_eq!(vec.capacity(), 100);
+        vec.shrink_to(200);
+        assert_eq!(vec.capacity(), 100);
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 3);
+        assert_eq!(vec, vector![0, 1, 2]);
+
+        vec.clear();
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 0);
+        vec.push(7);
+        assert_eq!(vec, vector![7]);
+
+        let mut units = vector![(), ()];
+        units.shrink_to_fit();
+        assert_eq!(units.capacity(), !0);
+    }
+
+    #[test]
+    fn test_vec_counting_alloc() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        #[derive(Clone, Default)]
+        struct Counting {
+            allocs: Rc<Cell<usize>>,
+            deallocs: Rc<Cell<usize>>,
+        }
+        unsafe impl Alloc for Counting {
+            unsafe fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocErr> {
+                self.allocs.set(self.allocs.get() + 1);
+                Global.alloc(layout)
+            }
+            unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+                self.deallocs.set(self.deallocs.get() + 1);
+                Global.dealloc(ptr, layout)
             }
         }
-    }
-}
-impl<T> Iterator for RawIter<T> {
-    type Item = T;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.start == self.end {
-            None
-        } else {
-            unsafe {
-                println!("before iter {:?}", self.start);
-                let res = ptr::read(self.start);
-                self.start = if mem::size_of::<T>() == 0 {
-                    (self.start as usize + 1) as *const _
-                } else {
-                    self.start.offset(1)
-                };
-                println!("after iter {:?}", self.start);
-                Some(res)
+
+        let counting = Counting::default();
+        {
+            let mut vec = Vector::new_in(counting.clone());
+            for i in 0..100 {
+                vec.push(i.to_string());
             }
+            let tail = vec.split_off(50);
+            let clone = tail.clone();
+            assert_eq!(clone.len(), 50);
+            let mut sized = Vector::with_capacity_in(10, counting.clone());
+            sized.push(1);
+            assert_eq!(vec.into_iter().count(), 50);
         }
+        assert!(counting.allocs.get() > 0);
+        assert_eq!(counting.allocs.get(), counting.deallocs.get());
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let item_size = mem::size_of::<T>();
-        let len = (self.end as usize - self.start as usize)
-            / if item_size == 0 { 1 } else { item_size };
-        (len, Some(len))
-    }
-}
-impl<T> DoubleEndedIterator for RawIter<T> {
-    fn next_back(&mut self) -> Option<T> {
-        if self.start == self.end {
-            None
-        } else {
-            unsafe {
-                self.end = if mem::size_of::<T>() == 0 {
-                    (self.end as usize - 1) as *const _
-                } else {
-                    self.end.offset(-1)
-                };
-                Some(ptr::read(self.end))
+    #[test]
+    fn test_vec_clone_from_reuses_buffer() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        #[derive(Clone, Default)]
+        struct Counting {
+            allocs: Rc<Cell<usize>>,
+        }
+        unsafe impl Alloc for Counting {
+            unsafe fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocErr> {
+                self.allocs.set(self.allocs.get() + 1);
+                Global.alloc(layout)
+            }
+            unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+                Global.dealloc(ptr, layout)
             }
         }
-    }
-}
 
+        let counting = Counting::default();
+        let mut src = Vector::new_in(counting.clone());
+        src.extend(vec![1, 2, 3]);
+        let mut dst = Vector::with_capacity_in(8, counting.clone());
+        dst.extend(vec![9, 9, 9, 9, 9]);
 
-pub struct IntoIter<T> {
-    _buff: RawVec<T>,
-    iter: RawIter<T>,
-}
-impl<T> Iterator for IntoIter<T> {
-    type Item = T;
-    fn next(&mut self) -> Option<T> { self.iter.next() }
-    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
-}
-impl<T> DoubleEndedIterator for IntoIter<T> {
-    fn next_back(&mut self) -> Option<T> { self.iter.next_back() }
-}
-impl<T> Drop for IntoIter<T> {
-    fn drop(&mut self) { for _ in &mut *self {} }
-}
+        let before = counting.allocs.get();
+        dst.clone_from(&src);
+        assert_eq!(dst, [1, 2, 3]);
+        assert_eq!(counting.allocs.get(), before);
 
+        src.push(4);
+        src.push(5);
+        let before = counting.allocs.get();
+        dst.clone_from(&src);
+        assert_eq!(dst, [1, 2, 3, 4, 5]);
+        assert_eq!(counting.allocs.get(), before);
 
-pub struct Drain<'a, T> {
-    _vec: PhantomData<&'a mut Vector<T>>,
-    iter: RawIter<T>,
-}
-impl<'a, T> Iterator for Drain<'a, T> {
-    type Item = T;
-    fn next(&mut self) -> Option<T> { self.iter.next() }
-    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
-}
-impl<'a, T> DoubleEndedIterator for Drain<'a, T> {
-    fn next_back(&mut self) -> Option<T> { self.iter.next_back() }
-}
-impl<'a, T> Drop for Drain<'a, T> {
-    fn drop(&mut self) { for _ in &mut *self {} }
-}
+        let mut small = Vector::new_in(counting.clone());
+        small.clone_from(&src);
+        assert_eq!(small, [1, 2, 3, 4, 5]);
+        assert_eq!(counting.allocs.get(), before + 1);
+    }
 
-macro_rules! vector {
-    ($($item:expr),*) => {
+    #[test]
+    fn test_vec_over_aligned() {
+        use std::cell::RefCell;
+        use std::collections::HashMap;
+        use std::rc::Rc;
+
+        #[repr(align(64))]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Cacheline([u8; 64]);
+
+        // remembers the layout of every live block and checks it is handed
+        // back unchanged
+        #[derive(Clone, Default)]
+        struct Tracking {
+            live: Rc<RefCell<HashMap<usize, Layout>>>,
+        }
+        unsafe impl Alloc for Tracking {
+            unsafe fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocErr> {
+                let ptr = Global.alloc(layout)?;
+                assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+                self.live.borrow_mut().insert(ptr.as_ptr() as usize, layout);
+                Ok(ptr)
+            }
+            unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+                let allocated = self.live.borrow_mut().remove(&(ptr.as_ptr() as usize));
+                assert_eq!(allocated, Some(layout));
+                Global.dealloc(ptr, layout)
+            }
+        }
+
+        let tracking = Tracking::default();
         {
-            let mut res = Vector::new();
-            $( res.push($item); )*
-            res
+            let mut vec = Vector::new_in(tracking.clone());
+            for i in 0..1000 {
+                vec.push(Cacheline([i as u8; 64]));
+            }
+            assert_eq!(vec.as_ptr() as usize % 64, 0);
+            assert_eq!(vec[999], Cacheline([(999 % 256) as u8; 64]));
+            vec.truncate(10);
+            vec.shrink_to_fit();
+            vec.reserve_exact(100);
+            assert_eq!(vec[9], Cacheline([9; 64]));
         }
-    };
-}
+        assert!(tracking.live.borrow().is_empty());
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_vec_growth_strategy() {
+        let mut vec = Vector::with_growth(GrowthStrategy::OnePointFive);
+        let mut caps = Vec::new();
+        for i in 0..20 {
+            vec.push(i);
+            if caps.last() != Some(&vec.capacity()) {
+                caps.push(vec.capacity());
+            }
+        }
+        assert_eq!(caps, [1, 2, 3, 4, 6, 9, 13, 19, 28]);
+        assert_eq!(vec.clone().capacity(), 28);
+        assert_eq!(vec, (0..20).collect::<Vector<_>>());
 
-    fn type_of<T>(_: &T) -> &str {
-        std::any::type_name::<T>()
+        let mut doubling = Vector::new();
+        for i in 0..5 {
+            doubling.push(i);
+        }
+        assert_eq!(doubling.capacity(), 8);
     }
 
     #[test]
-    fn test_vec_insert() {
-        let mut vec = Vector::new();
-        vec.push(1);
-        vec.push(2);
-        vec.push(3);
+    fn test_vec_growth_overflow() {
+        let max = RawVec::<u64>::max_cap();
+        assert_eq!(max, isize::MAX as usize / 8);
+        // the last step is clamped, past that growing fails
+        assert_eq!(GrowthStrategy::Double.grow_cap(max / 2 + 1, max), Some(max));
+        assert_eq!(GrowthStrategy::OnePointFive.grow_cap(max - 1, max), Some(max));
+        assert_eq!(GrowthStrategy::Double.grow_cap(max, max), None);
 
-        let val = vec.pop();
-        println!("whole enchilada {:?}", vec);
-        assert_eq!(val, Some(3));
+        let mut vec = vector![1_u64];
+        assert_eq!(vec.try_reserve(max), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(vec.try_reserve_exact(max), Err(TryReserveError::CapacityOverflow));
     }
 
     #[test]
-    fn test_vec_into_iter() {
-        let mut vec = Vector::new();
-        vec.push(1);
-        vec.push(2);
-        vec.push(3);
-        vec.push(4);
-        vec.push(5);
+    fn test_vec_hash_and_ord() {
+        use std::collections::hash_map::DefaultHasher;
 
-        println!("{:?}", vec);
-        for (idx, val) in vec.into_iter().enumerate() {
-            println!("found {:?}", val);
-            assert_eq!(val, idx + 1);
+        fn hash_of<H: Hash>(x: &H) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            x.hash(&mut hasher);
+            hasher.finish()
         }
+        let vec = vector![1, 2, 3];
+        assert_eq!(hash_of(&vec), hash_of(&vector![1, 2, 3]));
+        assert_eq!(hash_of(&vec), hash_of(&vec![1, 2, 3]));
+
+        assert!(vector![1, 2] < vector![1, 2, 3]);
+        assert!(vector![1, 3] > vector![1, 2, 3]);
+        assert_eq!(vector![1, 2].cmp(&vector![1, 2]), Ordering::Equal);
+        assert_eq!(vector![1.0, f64::NAN].partial_cmp(&vector![1.0, 2.0]), None);
+
+        let mut sorted = vec![vector![2_u8], vector![1, 9], vector![], vector![1]];
+        sorted.sort();
+        assert_eq!(sorted, vec![vector![], vector![1], vector![1, 9], vector![2]]);
     }
 
     #[test]
@@ -421,7 +2993,412 @@ mod tests {
         assert_eq!(back_vector, to_vector);
 
         let back_vec: Vec<_> = to_vector.into();
+        assert_eq!(back_vec, [10, 20, 30, 40]);
         println!("{}", type_of(&vector![1, 2, 3]));
-        assert_eq!(vector![1, 2, 3], vec![1, 2, 3].into())
+        assert_eq!(vector![1, 2, 3], Vector::from(vec![1, 2, 3]))
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_vec_serde_round_trip() {
+        let words: Vector<String> = vec!["a".to_string(), "b\"c".to_string()].into();
+        let json = serde_json::to_string(&words).unwrap();
+        assert_eq!(json, serde_json::to_string(&vec!["a", "b\"c"]).unwrap());
+        assert_eq!(serde_json::from_str::<Vector<String>>(&json).unwrap(), words);
+
+        let empty: Vector<String> = Vector::new();
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "[]");
+        assert_eq!(serde_json::from_str::<Vector<String>>("[]").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_vec_from_slice_and_array() {
+        assert_eq!(Vector::from(&[1, 2, 3][..]), vector![1, 2, 3]);
+        assert_eq!(Vector::from([1, 2, 3]), vector![1, 2, 3]);
+
+        let words = Vector::from(["a".to_string(), "b".to_string()]);
+        assert_eq!(words, vector!["a".to_string(), "b".to_string()]);
+
+        let empty: Vector<i32> = Vector::from(&[][..]);
+        assert_eq!(empty.cap(), 0);
+        let empty: Vector<i32> = Vector::from([]);
+        assert_eq!(empty.cap(), 0);
+    }
+
+    #[test]
+    fn test_vec_eq_std_types() {
+        let vec = vector![1, 2, 3];
+        assert_eq!(vec, vec![1, 2, 3]);
+        assert_eq!(vec![1, 2, 3], vec);
+        assert_eq!(vec, &[1, 2, 3][..]);
+        assert_eq!(&[1, 2, 3][..], vec);
+        assert_eq!(vec, [1, 2, 3]);
+        assert_eq!([1, 2, 3], vec);
+
+        assert_ne!(vec, vec![1, 2]);
+        assert_ne!(vec![1, 2, 3, 4], vec);
+        assert_ne!(vec, &[1, 2][..]);
+        assert_ne!(&[1, 2, 3, 4][..], vec);
+        assert_ne!(vec, [1, 2]);
+        assert_ne!([1, 2, 4], vec);
+    }
+
+    #[test]
+    fn test_vec_macro_repeat() {
+        let vec = vector![7; 5];
+        assert_eq!(vec, [7, 7, 7, 7, 7]);
+        assert_eq!(vec.cap(), 5);
+
+        let empty: Vector<String> = vector![String::new(); 0];
+        assert!(empty.is_empty());
+        assert_eq!(empty.cap(), 0);
+    }
+
+    #[test]
+    fn test_vec_spare_capacity_mut() {
+        let mut vec: Vector<u32> = vector![1];
+        vec.reserve(3);
+        let cap = vec.capacity();
+        let spare = vec.spare_capacity_mut();
+        assert_eq!(spare.len(), cap - 1);
+        for (i, slot) in spare[..3].iter_mut().enumerate() {
+            *slot = MaybeUninit::new(i as u32 + 2);
+        }
+        unsafe { vec.set_len(4); }
+        assert_eq!(vec, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_vec_raw_parts() {
+        let mut vec = vector!["a".to_string(), "b".to_string()];
+        vec.reserve(5);
+        let ptr = vec.as_mut_ptr();
+        assert_eq!(ptr as *const String, vec.as_ptr());
+        let (len, cap) = (vec.len(), vec.capacity());
+        mem::forget(vec);
+
+        let back = unsafe { Vector::from_raw_parts(ptr, len, cap) };
+        assert_eq!(back.capacity(), cap);
+        assert_eq!(back, ["a", "b"]);
+
+        let std_vec: Vec<String> = back.into();
+        assert_eq!(std_vec.capacity(), cap);
+        assert_eq!(std_vec, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_vec_into_boxed_slice() {
+        let mut vec = vector![1, 2, 3];
+        vec.reserve(10);
+        let boxed = vec.into_boxed_slice();
+        assert_eq!(&*boxed, &[1, 2, 3]);
+
+        let vec = Vector::from([4, 5]);
+        let ptr = vec.as_ptr();
+        let boxed = vec.into_boxed_slice();
+        assert_eq!(boxed.as_ptr(), ptr);
+        assert_eq!(boxed.len(), 2);
+
+        let empty: Vector<String> = Vector::new();
+        assert_eq!(empty.into_boxed_slice().len(), 0);
+    }
+
+    #[test]
+    fn test_vec_leak() {
+        let leaked: &'static mut [i32] = vector![1, 2, 3].leak();
+        leaked[0] = 9;
+        assert_eq!(leaked, &[9, 2, 3]);
+        unsafe { drop(Box::from_raw(leaked)); }
+    }
+
+    #[test]
+    fn test_vec_iter_mut() {
+        let mut vec = vector![1, 2, 3];
+        for x in vec.iter_mut() {
+            *x *= 10;
+        }
+        assert_eq!(vec.iter().collect::<Vec<_>>(), [&10, &20, &30]);
+
+        let mut empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.iter_mut().next(), None);
+    }
+
+    #[test]
+    fn test_vec_rotate() {
+        let mut vec = vector![1, 2, 3, 4, 5];
+        vec.rotate_left(2);
+        assert_eq!(vec, [3, 4, 5, 1, 2]);
+        vec.rotate_right(2);
+        assert_eq!(vec, [1, 2, 3, 4, 5]);
+        vec.rotate_right(2);
+        assert_eq!(vec, [4, 5, 1, 2, 3]);
+        vec.rotate_left(0);
+        vec.rotate_left(5);
+        assert_eq!(vec, [4, 5, 1, 2, 3]);
+
+        vec.reverse();
+        assert_eq!(vec, [3, 2, 1, 5, 4]);
+        vec.swap(0, 4);
+        vec.swap(1, 1);
+        assert_eq!(vec, [4, 2, 1, 5, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_vec_rotate_out_of_bounds() {
+        let mut vec = vector![1, 2, 3];
+        vec.rotate_left(4);
+    }
+
+    #[test]
+    fn test_vec_try_remove() {
+        let mut empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.try_remove(0), None);
+        assert_eq!(empty.try_swap_remove(0), None);
+
+        let mut vec = vector![1, 2, 3, 4];
+        assert_eq!(vec.try_remove(4), None);
+        assert_eq!(vec.try_remove(1), Some(2));
+        assert_eq!(vec, [1, 3, 4]);
+        assert_eq!(vec.try_swap_remove(3), None);
+        assert_eq!(vec.try_swap_remove(0), Some(1));
+        assert_eq!(vec, [4, 3]);
+    }
+
+    #[test]
+    fn test_vec_push_within_capacity() {
+        let mut vec = Vector::with_capacity(2);
+        assert_eq!(vec.push_within_capacity("a".to_string()), Ok(()));
+        assert_eq!(vec.push_within_capacity("b".to_string()), Ok(()));
+        assert_eq!(vec.push_within_capacity("c".to_string()), Err("c".to_string()));
+        assert_eq!(vec.capacity(), 2);
+        assert_eq!(vec, ["a", "b"]);
+
+        let mut empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.push_within_capacity(1), Err(1));
+        assert_eq!(empty.capacity(), 0);
+    }
+
+    #[test]
+    fn test_vec_pop_if() {
+        let mut vec = vector!["a", " ", " "];
+        assert_eq!(vec.pop_if(|s| s.trim().is_empty()), Some(" "));
+        assert_eq!(vec.pop_if(|s| s.trim().is_empty()), Some(" "));
+        assert_eq!(vec.pop_if(|s| s.trim().is_empty()), None);
+        assert_eq!(vec, ["a"]);
+
+        let mut empty: Vector<i32> = Vector::new();
+        assert_eq!(empty.pop_if(|_| true), None);
+    }
+
+    #[test]
+    fn test_vec_into_iter_as_slice() {
+        let mut iter = vector![1, 2, 3, 4, 5, 6].into_iter();
+        assert_eq!(iter.as_slice(), &[1, 2, 3, 4, 5, 6]);
+        iter.next();
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.as_slice(), &[3, 4, 5]);
+        iter.as_mut_slice()[0] = 30;
+        assert_eq!(iter.collect::<Vec<_>>(), [30, 4, 5]);
+
+        let mut units = vector![(), (), ()].into_iter();
+        units.next();
+        assert_eq!(units.as_slice().len(), 2);
+    }
+
+    #[test]
+    fn test_vec_index() {
+        let mut vec = vector![1, 2, 3, 4];
+        assert_eq!(vec[0], 1);
+        vec[3] = 40;
+        assert_eq!(vec[3], 40);
+
+        assert_eq!(&vec[1..3], &[2, 3]);
+        assert_eq!(&vec[2..], &[3, 40]);
+        assert_eq!(&vec[..=1], &[1, 2]);
+        assert!(vec[4..].is_empty());
+        vec[..2].copy_from_slice(&[10, 20]);
+        assert_eq!(vec, [10, 20, 3, 40]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vector index 4 out of bounds 4")]
+    fn test_vec_index_out_of_bounds() {
+        let vec = vector![1, 2, 3, 4];
+        let _ = vec[4];
+    }
+
+    #[test]
+    #[should_panic(expected = "Vector range end 5 out of bounds 4")]
+    fn test_vec_range_index_out_of_bounds() {
+        let vec = vector![1, 2, 3, 4];
+        let _ = &vec[1..5];
+    }
+
+    #[test]
+    fn test_vec_default() {
+        #[derive(Default)]
+        struct Config {
+            ports: Vector<u32>,
+        }
+        let config = Config::default();
+        assert!(config.ports.is_empty());
+        assert_eq!(config.ports.capacity(), 0);
+    }
+
+    #[test]
+    fn test_vec_into_std_vec_edge_cases() {
+        let units: Vec<()> = vector![(), (), ()].into();
+        assert_eq!(units.len(), 3);
+        let units: Vec<()> = Vector::from(units).into();
+        assert_eq!(units.len(), 3);
+
+        let empty: Vec<String> = Vector::new().into();
+        assert!(empty.is_empty());
+        assert_eq!(empty.capacity(), 0);
+
+        let mut std_vec = Vec::with_capacity(10);
+        std_vec.extend_from_slice(&[1, 2, 3]);
+        let back: Vec<i32> = Vector::from(std_vec).into();
+        assert_eq!(back, [1, 2, 3]);
+        assert_eq!(back.capacity(), 10);
+    }
+
+    #[test]
+    fn test_vec_concat_join() {
+        let frames = vector![vector![1_u8, 2], vector![], vector![3, 4, 5]];
+        let flat = frames.concat();
+        assert_eq!(flat, [1, 2, 3, 4, 5]);
+        assert_eq!(flat.capacity(), 5);
+
+        let joined = frames.join(&0);
+        assert_eq!(joined, [1, 2, 0, 0, 3, 4, 5]);
+        assert_eq!(joined.capacity(), 7);
+
+        let words = vector![&b"ab"[..], &b"c"[..]];
+        assert_eq!(words.join(&b' '), *b"ab c");
+
+        let empty: Vector<Vector<u8>> = Vector::new();
+        assert!(empty.concat().is_empty());
+        assert!(empty.join(&0).is_empty());
+    }
+
+    #[test]
+    fn test_vec_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Vector<i32>>();
+        assert_send_sync::<IntoIter<String>>();
+        assert_send_sync::<Drain<'static, String>>();
+
+        let vec = vector![1, 2, 3];
+        let sum = std::thread::spawn(move || vec.iter().sum::<i32>()).join().unwrap();
+        assert_eq!(sum, 6);
+
+        let iter = vector![4, 5].into_iter();
+        let rest = std::thread::spawn(move || iter.collect::<Vec<_>>()).join().unwrap();
+        assert_eq!(rest, [4, 5]);
+    }
+
+    #[test]
+    fn test_vec_matches_std_vec() {
+        let mut vec = Vector::new();
+        let mut std_vec = Vec::new();
+        for i in 0..200 {
+            vec.push(i.to_string());
+            std_vec.push(i.to_string());
+            if i % 3 == 0 {
+                assert_eq!(vec.remove(i / 2), std_vec.remove(i / 2));
+            }
+            if i % 7 == 0 {
+                vec.insert(i / 3, "x".to_string());
+                std_vec.insert(i / 3, "x".to_string());
+            }
+        }
+        assert_eq!(vec, std_vec);
+
+        vec.truncate(50);
+        std_vec.truncate(50);
+        vec.shrink_to_fit();
+        vec.reserve_exact(10);
+        assert_eq!(vec.capacity(), 60);
+        assert_eq!(vec.drain(10..20).collect::<Vec<_>>(), std_vec.drain(10..20).collect::<Vec<_>>());
+        assert_eq!(Vec::from(vec), std_vec);
+
+        // the trait's default `realloc` moves the block through `alloc`
+        struct NoRealloc;
+        unsafe impl Alloc for NoRealloc {
+            unsafe fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocErr> {
+                Global.alloc(layout)
+            }
+            unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+                Global.dealloc(ptr, layout)
+            }
+        }
+        let mut vec = Vector::new_in(NoRealloc);
+        vec.extend(0..100);
+        vec.truncate(3);
+        vec.shrink_to_fit();
+        assert_eq!(vec, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_vec_extract_if() {
+        let mut vec: Vector<_> = (1..=10).collect();
+        let evens: Vector<_> = vec.extract_if(|x| *x % 2 == 0).collect();
+        assert_eq!(evens, [2, 4, 6, 8, 10]);
+        assert_eq!(vec, [1, 3, 5, 7, 9]);
+
+        // dropped early, the unchecked tail is kept in order
+        let mut vec = vector!["a".to_string(), "bb".to_string(), "c".to_string(), "dd".to_string()];
+        let mut iter = vec.extract_if(|s| s.len() == 2);
+        assert_eq!(iter.next(), Some("bb".to_string()));
+        drop(iter);
+        assert_eq!(vec, ["a", "c", "dd"]);
+
+        // leaked, the vector forgets its elements rather than exposing holes
+        let mut vec = vector![1, 2, 3];
+        mem::forget(vec.extract_if(|x| *x == 2));
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_vec_extract_if_panic() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut vec = (0..6).map(|i| (i, Rc::clone(&counter))).collect::<Vector<_>>();
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _removed: Vec<_> = vec.extract_if(|(i, _)| {
+                assert!(*i != 3);
+                *i % 2 == 0
+            }).collect();
+        }));
+        assert!(res.is_err());
+        // 0 and 2 were extracted and dropped, 3 panicked and is kept
+        assert_eq!(vec.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [1, 3, 4, 5]);
+        assert_eq!(Rc::strong_count(&counter), 5);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vector range end overflows usize")]
+    fn test_vec_drain_inclusive_max() {
+        let mut vec = vector![1, 2, 3, 4, 5];
+        vec.drain(0..=usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vector range end overflows usize")]
+    fn test_vec_index_inclusive_max() {
+        let vec = vector![1, 2, 3, 4, 5];
+        let _ = &vec[0..=usize::MAX];
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot read a zero sized type from bytes")]
+    fn test_vec_from_bytes_copy_zst() {
+        unsafe { Vector::<()>::from_bytes_copy(&[]) };
     }
 }